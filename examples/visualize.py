@@ -24,7 +24,7 @@ def create_enhanced_visualization(
         output_file: Output HTML file
         search_query: Optional search query to highlight results
         highlight_clusters: Optional list of cluster IDs to highlight
-        min_significance: Minimum significance to display (0-9)
+        min_significance: Minimum significance to display (0-255)
         show_edge_weights: Whether to show edge weights (requires computation)
     """
     
@@ -134,7 +134,7 @@ def create_enhanced_visualization(
             border_width = 1
         
         # Scale size by significance (bigger = more significant)
-        base_size = significance * 3 + 15
+        base_size = significance / 255 * 27 + 15
         
         # Boost size for search results
         if is_search_result:
@@ -144,7 +144,7 @@ def create_enhanced_visualization(
         tooltip_parts = [
             f"Node {node_id}",
             f"Cluster: {cluster_id if cluster_id else 'None'}",
-            f"Significance: {significance}/9",
+            f"Significance: {significance}/255",
         ]
         
         if is_search_result:
@@ -368,7 +368,7 @@ def main():
     parser.add_argument('--output', default='enhanced_graph.html', help='Output HTML file')
     parser.add_argument('--search', help='Search query to highlight results')
     parser.add_argument('--clusters', help='Comma-separated cluster IDs to highlight')
-    parser.add_argument('--min-sig', type=int, default=0, help='Minimum significance (0-9)')
+    parser.add_argument('--min-sig', type=int, default=0, help='Minimum significance (0-255)')
     parser.add_argument('--no-edge-weights', action='store_true', help='Disable edge weight visualization')
     
     args = parser.parse_args()
@@ -269,9 +269,9 @@ function handleGraphSnapshot(data) {
  * Add a single node to the graph
  */
 function addNodeToGraph(node) {
-    // Calculate node size based on significance (0-9)
+    // Calculate node size based on significance (0-255)
     const size = CONFIG.NODE.minSize +
-        (node.significance / 9) * (CONFIG.NODE.maxSize - CONFIG.NODE.minSize);
+        (node.significance / 255) * (CONFIG.NODE.maxSize - CONFIG.NODE.minSize);
 
     // Add node to Graphology
     graph.addNode(node.id.toString(), {
@@ -593,7 +593,7 @@ function setupHoverEffects() {
         document.getElementById('node-info-title').textContent = `Node ${node}`;
         document.getElementById('node-info-content').textContent = attrs.content || attrs.label;
         document.getElementById('node-info-cluster').textContent = attrs.clusterId ?? 'None';
-        document.getElementById('node-info-sig').textContent = `${attrs.significance}/9`;
+        document.getElementById('node-info-sig').textContent = `${attrs.significance}/255`;
 
         nodeInfoEl.classList.remove('hidden');
     });
@@ -12,7 +12,7 @@
 // └─────────────┘     └─────────────┘     └─────────────┘
 //
 // Usage:
-//   cargo run --bin spider-server -- --db path/to/spider.db --port 3000
+//   cargo run --bin spider-server -- --db path/to/spider.db --port 3000 [--preview-len 30]
 //
 // ============================================================================
 
@@ -52,7 +52,7 @@ pub struct GraphNode {
     pub label: String,
     /// Full content of the node
     pub content: String,
-    /// Significance score (0-9)
+    /// Significance score (0-255)
     pub significance: u8,
     /// Cluster ID this node belongs to (if any)
     pub cluster_id: Option<u64>,
@@ -141,11 +141,14 @@ pub struct AppState {
     
     /// Cached graph data (refreshed on notify)
     pub graph_cache: RwLock<Option<GraphData>>,
+
+    /// Number of content characters shown in node labels
+    pub preview_len: usize,
 }
 
 impl AppState {
-    /// Create new application state with the given database path
-    pub fn new(db_path: PathBuf) -> Arc<Self> {
+    /// Create new application state with the given database path and label length
+    pub fn new(db_path: PathBuf, preview_len: usize) -> Arc<Self> {
         // Create a broadcast channel with buffer size 100
         // This means we can have up to 100 pending messages before older ones are dropped
         let (tx, _rx) = broadcast::channel(100);
@@ -154,6 +157,7 @@ impl AppState {
             db_path,
             broadcast_tx: tx,
             graph_cache: RwLock::new(None),
+            preview_len,
         })
     }
     
@@ -162,10 +166,11 @@ impl AppState {
     pub async fn load_graph_data(&self) -> Result<GraphData, String> {
         // Read the database file
         let db_path = self.db_path.clone();
+        let preview_len = self.preview_len;
         
         // We need to spawn a blocking task because file I/O is synchronous
         let result = tokio::task::spawn_blocking(move || {
-            load_graph_from_db(&db_path)
+            load_graph_from_db(&db_path, preview_len)
         })
         .await
         .map_err(|e| format!("Task join error: {}", e))?;
@@ -198,10 +203,12 @@ impl AppState {
 // ============================================================================
 
 /// Load graph data from a Spider database file
-/// This function reads the binary .db file format
-fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
+/// This function reads the binary .db file format. Labels are the first
+/// `preview_len` characters of the content, with "…" appended when cut short
+/// (the same rule as `SpiderDB.get_all_graph_data`).
+fn load_graph_from_db(db_path: &PathBuf, preview_len: usize) -> Result<GraphData, String> {
     use std::fs::File;
-    use std::io::BufReader;
+    use std::io::{BufReader, Read, Seek, SeekFrom};
     
     // Check if file exists
     if !db_path.exists() {
@@ -211,7 +218,25 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
     // Open and deserialize the database snapshot
     let file = File::open(db_path)
         .map_err(|e| format!("Failed to open database: {}", e))?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+
+    // Newer files start with a magic + format version (see `SNAPSHOT_MAGIC`
+    // in db.rs); older ones are bare bincode in format version 0
+    let mut magic = [0u8; 4];
+    let version = if reader.read_exact(&mut magic).is_ok() && &magic == b"SPDB" {
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)
+            .map_err(|e| format!("Failed to read database header: {}", e))?;
+        let version = u32::from_le_bytes(version);
+        if version > 1 {
+            return Err(format!("Database format version {} is newer than this server supports", version));
+        }
+        version
+    } else {
+        reader.seek(SeekFrom::Start(0))
+            .map_err(|e| format!("Failed to read database: {}", e))?;
+        0
+    };
     
     // The database uses bincode serialization
     // We need to match the SpiderSnapshot struct from db.rs
@@ -221,6 +246,8 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
         data_heap: Vec<u8>,
         edge_list: Vec<Vec<u64>>,
         #[allow(dead_code)]
+        edge_created: Vec<Vec<u64>>,
+        #[allow(dead_code)]
         embeddings: Vec<Vec<f32>>,
         clusters: Option<Vec<Cluster>>,
         #[allow(dead_code)]
@@ -240,8 +267,42 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
         last_access_ts: u64,
         #[allow(dead_code)]
         access_count: u32,
+        #[allow(dead_code)]
+        recent_access: f32,
+        significance: u8,
+        flags: u8,
+        #[allow(dead_code)]
+        created_ts: u64,
+        #[allow(dead_code)]
+        ttl_secs: u32,
+    }
+
+    /// Format version 0 layouts (see `SnapshotV0` in db.rs)
+    #[derive(Deserialize)]
+    struct SnapshotV0 {
+        headers: Vec<NodeHeaderV0>,
+        data_heap: Vec<u8>,
+        edge_list: Vec<Vec<u64>>,
+        embeddings: Vec<Vec<f32>>,
+        clusters: Option<Vec<Cluster>>,
+        cluster_config: ClusterConfig,
+    }
+
+    #[derive(Deserialize)]
+    struct NodeHeaderV0 {
+        id: u64,
+        data_offset: u64,
+        data_len: u32,
+        edge_start: u32,
+        edge_count: u32,
+        last_access_ts: u64,
+        access_count: u32,
         significance: u8,
     }
+
+    /// Match `storage::FLAG_*` in the library
+    const FLAG_COMPRESSED: u8 = 1 << 0;
+    const FLAG_DELETED: u8 = 1 << 1;
     
     #[derive(Deserialize)]
     struct Cluster {
@@ -270,8 +331,37 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
         similarity_threshold: f32,
     }
     
-    let snapshot: SpiderSnapshot = bincode::deserialize_from(reader)
-        .map_err(|e| format!("Failed to deserialize database: {}", e))?;
+    let snapshot: SpiderSnapshot = if version == 0 {
+        let old: SnapshotV0 = bincode::deserialize_from(reader)
+            .map_err(|e| format!("Failed to deserialize database: {}", e))?;
+        // Same header defaults as the library's migration; edge creation
+        // times aren't used here, so they're left at 0
+        SpiderSnapshot {
+            headers: old.headers.into_iter().map(|h| NodeHeader {
+                id: h.id,
+                data_offset: h.data_offset,
+                data_len: h.data_len,
+                edge_start: h.edge_start,
+                edge_count: h.edge_count,
+                last_access_ts: h.last_access_ts,
+                access_count: h.access_count,
+                recent_access: h.access_count as f32,
+                significance: h.significance,
+                flags: 0,
+                created_ts: h.last_access_ts,
+                ttl_secs: 0,
+            }).collect(),
+            data_heap: old.data_heap,
+            edge_created: old.edge_list.iter().map(|edges| vec![0; edges.len()]).collect(),
+            edge_list: old.edge_list,
+            embeddings: old.embeddings,
+            clusters: old.clusters,
+            cluster_config: old.cluster_config,
+        }
+    } else {
+        bincode::deserialize_from(reader)
+            .map_err(|e| format!("Failed to deserialize database: {}", e))?
+    };
     
     // Build node -> cluster mapping
     let mut node_cluster_map: HashMap<u64, u64> = HashMap::new();
@@ -299,11 +389,25 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
     // Convert headers to GraphNodes
     let mut nodes = Vec::new();
     for header in &snapshot.headers {
+        if header.flags & FLAG_DELETED != 0 {
+            continue;
+        }
+
         // Extract content from data heap
         let start = header.data_offset as usize;
         let end = start + header.data_len as usize;
         let content = if end <= snapshot.data_heap.len() {
-            String::from_utf8_lossy(&snapshot.data_heap[start..end]).to_string()
+            let stored = &snapshot.data_heap[start..end];
+            if header.flags & FLAG_COMPRESSED != 0 {
+                use std::io::Read;
+                let mut inflated = Vec::new();
+                flate2::read::DeflateDecoder::new(stored)
+                    .read_to_end(&mut inflated)
+                    .map_err(|e| format!("Failed to decompress node {}: {}", header.id, e))?;
+                String::from_utf8_lossy(&inflated).to_string()
+            } else {
+                String::from_utf8_lossy(stored).to_string()
+            }
         } else {
             format!("Node {}", header.id)
         };
@@ -318,8 +422,11 @@ fn load_graph_from_db(db_path: &PathBuf) -> Result<GraphData, String> {
             "#808080".to_string() // Gray for unclustered nodes
         };
         
-        // Create short label (first 30 chars)
-        let label = content.chars().take(30).collect::<String>();
+        // Create short label (first preview_len chars)
+        let mut label = content.chars().take(preview_len).collect::<String>();
+        if label.len() < content.len() {
+            label.push('…');
+        }
         
         nodes.push(GraphNode {
             id: header.id,
@@ -563,8 +670,9 @@ async fn main() {
     // Default values
     let mut db_path = PathBuf::from("spider_graph.db");
     let mut port: u16 = 3000;
+    let mut preview_len: usize = 30;
     
-    // Parse --db and --port arguments
+    // Parse --db, --port and --preview-len arguments
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
@@ -586,6 +694,15 @@ async fn main() {
                     std::process::exit(1);
                 }
             }
+            "--preview-len" => {
+                if i + 1 < args.len() {
+                    preview_len = args[i + 1].parse().unwrap_or(30);
+                    i += 2;
+                } else {
+                    eprintln!("Error: --preview-len requires a number");
+                    std::process::exit(1);
+                }
+            }
             _ => {
                 i += 1;
             }
@@ -597,7 +714,7 @@ async fn main() {
     println!("   Port: {}", port);
     
     // Create application state
-    let state = AppState::new(db_path);
+    let state = AppState::new(db_path, preview_len);
     
     // Pre-load the graph into cache
     if let Err(e) = state.load_graph_data().await {
@@ -636,3 +753,27 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_baseline_v0_database() {
+        let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/baseline_v0.db"));
+        let graph = load_graph_from_db(&path, 30).unwrap();
+        let contents: Vec<&str> = graph.nodes.iter().map(|n| n.content.as_str()).collect();
+        assert_eq!(contents, ["alpha", "beta", "gamma", "delta"]);
+        assert_eq!(graph.nodes[0].significance, 200);
+        assert!(!graph.edges.is_empty());
+    }
+
+    #[test]
+    fn labels_are_cut_to_preview_len() {
+        let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/baseline_v0.db"));
+        let graph = load_graph_from_db(&path, 4).unwrap();
+        let labels: Vec<&str> = graph.nodes.iter().map(|n| n.label.as_str()).collect();
+        assert_eq!(labels, ["alph…", "beta", "gamm…", "delt…"]);
+        assert_eq!(graph.nodes[0].content, "alpha");
+    }
+}
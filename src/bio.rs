@@ -1,30 +1,290 @@
+use crate::error::SpiderError;
 use crate::storage::NodeHeader;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Calculates the "Life Score" of a node based on its biological metrics.
-///
-/// Formula: ((Freq * 2) + (Sig * 10)) / (Time + 2)^1.8
-///
-/// # Arguments
+/// Current wall-clock time as a Unix timestamp in seconds.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Tunable constants of the life score formula (see `BioConfig::life_score`).
 ///
-/// * `header` - The NodeHeader containing bio-metrics.
+/// Construct through `BioConfig::new`, which rejects values that would make
+/// scores NaN, infinite or negative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BioConfig {
+    /// Weight of the lifetime access count
+    pub frequency_weight: f32,
+    /// Weight of the node's significance
+    pub significance_weight: f32,
+    /// Hours added to the time since last access; keeps the denominator positive
+    pub time_offset_hours: f32,
+    /// Power the (offset) age is raised to; higher decays faster
+    pub decay_exponent: f32,
+    /// Floor on the life score as seen by search ranking, so nodes that have
+    /// never been read still get some bio score. Vacuum uses the raw score.
+    pub min_life_score: f32,
+    /// Half-life of the decayed (recent) access counter, in hours
+    pub recent_access_half_life_hours: f32,
+}
+
+impl Default for BioConfig {
+    fn default() -> Self {
+        BioConfig {
+            frequency_weight: 2.0,
+            significance_weight: 10.0,
+            time_offset_hours: 2.0,
+            decay_exponent: 1.8,
+            min_life_score: 0.0,
+            recent_access_half_life_hours: 24.0,
+        }
+    }
+}
+
+impl BioConfig {
+    /// Validated constructor: weights and the life score floor must be finite
+    /// and >= 0, the time offset, decay exponent and recent-access half-life
+    /// finite and > 0.
+    pub fn new(
+        frequency_weight: f32,
+        significance_weight: f32,
+        time_offset_hours: f32,
+        decay_exponent: f32,
+        min_life_score: f32,
+        recent_access_half_life_hours: f32,
+    ) -> Result<Self, SpiderError> {
+        let invalid = |reason: String| Err(SpiderError::InvalidConfig(reason));
+        for (name, weight) in [
+            ("frequency_weight", frequency_weight),
+            ("significance_weight", significance_weight),
+            ("min_life_score", min_life_score),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                return invalid(format!("{} must be a finite value >= 0, got {}", name, weight));
+            }
+        }
+        if !time_offset_hours.is_finite() || time_offset_hours <= 0.0 {
+            return invalid(format!("time_offset_hours must be a finite value > 0, got {}", time_offset_hours));
+        }
+        if !decay_exponent.is_finite() || decay_exponent <= 0.0 {
+            return invalid(format!("decay_exponent must be a finite value > 0, got {}", decay_exponent));
+        }
+        if !recent_access_half_life_hours.is_finite() || recent_access_half_life_hours <= 0.0 {
+            return invalid(format!(
+                "recent_access_half_life_hours must be a finite value > 0, got {}", recent_access_half_life_hours
+            ));
+        }
+        Ok(BioConfig {
+            frequency_weight,
+            significance_weight,
+            time_offset_hours,
+            decay_exponent,
+            min_life_score,
+            recent_access_half_life_hours,
+        })
+    }
+
+    /// Life score of `header` at time `now` (Unix seconds):
+    /// ((Freq * frequency_weight) + (Sig * significance_weight)) / (Time + time_offset_hours)^decay_exponent
+    pub fn life_score(&self, header: &NodeHeader, now: u64) -> f32 {
+        // Avoid division by zero and negative time if clock skew
+        let delta_t_hours = (now.saturating_sub(header.last_access_ts) as f32) / 3600.0;
+
+        let numerator = (header.access_count as f32 * self.frequency_weight)
+            + (header.significance as f32 * self.significance_weight);
+        let denominator = (delta_t_hours + self.time_offset_hours).powf(self.decay_exponent);
+
+        numerator / denominator
+    }
+
+    /// The node's recent-access estimate decayed forward to `now`.
+    ///
+    /// Each access adds 1.0 and the total halves every
+    /// `recent_access_half_life_hours`, so a node hammered long ago ends up
+    /// lower than one read a few times today.
+    pub fn decayed_access(&self, header: &NodeHeader, now: u64) -> f32 {
+        let hours = now.saturating_sub(header.last_access_ts) as f32 / 3600.0;
+        header.recent_access * self.recent_access_decay(hours)
+    }
+
+    /// Decayed access count of a node read steadily `reads_per_day` times a
+    /// day, once it has settled: rate per hour * half-life / ln 2.
+    pub fn steady_recent_access(&self, reads_per_day: f32) -> f32 {
+        reads_per_day / 24.0 * self.recent_access_half_life_hours / std::f32::consts::LN_2
+    }
+
+    /// Factor the recent-access counter shrinks by over `hours`.
+    fn recent_access_decay(&self, hours: f32) -> f32 {
+        0.5f32.powf(hours / self.recent_access_half_life_hours)
+    }
+}
+
+/// Live access metrics for one node, updatable through a shared reference.
 ///
-/// # Returns
+/// `NodeHeader` stays plain `#[repr(C)]` data, so the hot-path counters live
+/// in this parallel structure instead and are folded back into a header copy
+/// by `apply_to`. All operations use relaxed ordering: each field is updated
+/// atomically on its own, so concurrent readers never lose a count, but the
+/// three fields aren't updated as one unit.
+#[derive(Debug, Default)]
+pub struct AccessCounters {
+    access_count: AtomicU32,
+    last_access_ts: AtomicU64,
+    /// `f32` bits of the decayed access count as of `last_access_ts`
+    recent_access: AtomicU32,
+}
+
+impl AccessCounters {
+    /// Seeds the counters from a header's persisted metrics.
+    pub fn from_header(header: &NodeHeader) -> Self {
+        AccessCounters {
+            access_count: AtomicU32::new(header.access_count),
+            last_access_ts: AtomicU64::new(header.last_access_ts),
+            recent_access: AtomicU32::new(header.recent_access.to_bits()),
+        }
+    }
+
+    /// Records one access at time `now` on all bio-metrics, decaying the
+    /// recent-access counter per `config`.
+    pub fn record(&self, now: u64, config: &BioConfig) {
+        self.access_count.fetch_add(1, Ordering::Relaxed);
+        let last = self.last_access_ts.fetch_max(now, Ordering::Relaxed);
+        let decay = config.recent_access_decay(now.saturating_sub(last) as f32 / 3600.0);
+        let _ = self.recent_access.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f32::from_bits(bits) * decay + 1.0).to_bits())
+        });
+    }
+
+    /// Moves `last_access_ts` up to `now` without counting an access;
+    /// `recent_access` decays over the skipped time as it would on `record`.
+    pub fn touch(&self, now: u64, config: &BioConfig) {
+        let last = self.last_access_ts.fetch_max(now, Ordering::Relaxed);
+        let decay = config.recent_access_decay(now.saturating_sub(last) as f32 / 3600.0);
+        let _ = self.recent_access.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f32::from_bits(bits) * decay).to_bits())
+        });
+    }
+
+    /// Adds `count` accesses without touching the timestamps (saturating).
+    pub fn add_count(&self, count: u32) {
+        let _ = self.access_count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| {
+            Some(c.saturating_add(count))
+        });
+    }
+
+    /// Copies the current counter values into `header`.
+    pub fn apply_to(&self, header: &mut NodeHeader) {
+        header.access_count = self.access_count.load(Ordering::Relaxed);
+        header.last_access_ts = self.last_access_ts.load(Ordering::Relaxed);
+        header.recent_access = f32::from_bits(self.recent_access.load(Ordering::Relaxed));
+    }
+}
+
+/// A single retention rule shared by vacuum and search.
 ///
-/// * `f32` - The calculated life score.
-pub fn calc_life_score(header: &NodeHeader) -> f32 {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// A node not accessed for more than `max_age_hours` is expired unless its
+/// significance is at least `min_significance`. Expired nodes are hidden from
+/// search and flagged by vacuum, so the two can't drift apart.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    pub max_age_hours: f32,
+    pub min_significance: u8,
+}
 
-    // Avoid division by zero and negative time if clock skew
-    let delta_t_hours = (now.saturating_sub(header.last_access_ts) as f32) / 3600.0;
+impl RetentionPolicy {
+    /// Returns true if the node is still retained at time `now`.
+    pub fn retains(&self, header: &NodeHeader, now: u64) -> bool {
+        let age_hours = now.saturating_sub(header.last_access_ts) as f32 / 3600.0;
+        age_hours <= self.max_age_hours || header.significance >= self.min_significance
+    }
+}
+
+/// Which nodes `trim_to_capacity` evicts first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Lowest life score first (the default)
+    LifeScore,
+    /// Least recently accessed first
+    Lru,
+    /// Least frequently accessed first, least recent among equals
+    Lfu,
+}
 
-    let numerator = (header.access_count as f32 * 2.0) + (header.significance as f32 * 10.0);
-    let denominator = (delta_t_hours + 2.0).powf(1.8);
+impl EvictionPolicy {
+    /// Parses "life_score", "lru" or "lfu" (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "life_score" => Some(EvictionPolicy::LifeScore),
+            "lru" => Some(EvictionPolicy::Lru),
+            "lfu" => Some(EvictionPolicy::Lfu),
+            _ => None,
+        }
+    }
 
-    numerator / denominator
+    /// Eviction key of `header` at time `now`: lower keys are evicted first.
+    pub fn key(&self, header: &NodeHeader, now: u64, config: &BioConfig) -> (f64, u64) {
+        match self {
+            EvictionPolicy::LifeScore => (config.life_score(header, now) as f64, header.last_access_ts),
+            EvictionPolicy::Lru => (header.last_access_ts as f64, 0),
+            EvictionPolicy::Lfu => (header.access_count as f64, header.last_access_ts),
+        }
+    }
 }
 
 // TODO: Implement "RL Weight Tuning" here later.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_decay_exponent_is_rejected() {
+        let with_exponent = |exponent: f32| BioConfig::new(2.0, 10.0, 2.0, exponent, 0.0, 24.0);
+
+        for exponent in [0.0, -1.8, f32::NAN, f32::INFINITY] {
+            assert!(matches!(with_exponent(exponent), Err(SpiderError::InvalidConfig(_))), "{}", exponent);
+        }
+        assert!(with_exponent(1.8).is_ok());
+        assert!(BioConfig::new(2.0, 10.0, 0.0, 1.8, 0.0, 24.0).is_err());
+        assert!(BioConfig::new(-2.0, 10.0, 2.0, 1.8, 0.0, 24.0).is_err());
+    }
+
+    #[test]
+    fn recent_access_half_life_is_configurable() {
+        let with_half_life = |hours: f32| BioConfig::new(2.0, 10.0, 2.0, 1.8, 0.0, hours);
+        for hours in [0.0, -24.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(with_half_life(hours), Err(SpiderError::InvalidConfig(_))), "{}", hours);
+        }
+
+        let burst = |config: &BioConfig| {
+            let counters = AccessCounters::default();
+            for _ in 0..10 {
+                counters.record(0, config);
+            }
+            let mut header = NodeHeader {
+                id: 0,
+                data_offset: 0,
+                data_len: 0,
+                edge_start: 0,
+                edge_count: 0,
+                last_access_ts: 0,
+                access_count: 0,
+                recent_access: 0.0,
+                significance: 0,
+                flags: 0,
+                created_ts: 0,
+                ttl_secs: 0,
+            };
+            counters.apply_to(&mut header);
+            config.decayed_access(&header, 24 * 3600)
+        };
+        let daily = BioConfig::default();
+        let six_hourly = with_half_life(6.0).unwrap();
+        assert!((burst(&daily) - 5.0).abs() < 1e-4);
+        assert!((burst(&six_hourly) - 10.0 / 16.0).abs() < 1e-4);
+    }
+}
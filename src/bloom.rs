@@ -0,0 +1,80 @@
+/// A fixed-size Bloom filter over precomputed 64-bit hashes.
+///
+/// Answers "definitely absent" or "possibly present". Callers keep an exact
+/// structure behind it and only consult that on a positive hit.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Sizes the filter for `expected_items` at the given false-positive rate.
+    ///
+    /// Uses the standard optimum: m = -n·ln(p) / ln(2)², k = (m/n)·ln(2).
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let p = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_bits = ((-n * p.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Records a hash as present.
+    pub fn insert(&mut self, hash: u64) {
+        for bit in self.bit_positions(hash) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns false only if the hash was never inserted.
+    pub fn contains(&self, hash: u64) -> bool {
+        self.bit_positions(hash)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Kirsch–Mitzenmacher double hashing: bit_i = h1 + i·h2 (mod m)
+    fn bit_positions(&self, hash: u64) -> impl Iterator<Item = u64> {
+        let h1 = hash;
+        // SplitMix64 finalizer to derive an independent second hash (forced odd)
+        let mut h2 = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h2 = (h2 ^ (h2 >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h2 = (h2 ^ (h2 >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h2 = (h2 ^ (h2 >> 31)) | 1;
+
+        let m = self.num_bits;
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::content_hash;
+
+    fn hash(i: usize) -> u64 {
+        content_hash(format!("node content {}", i).as_bytes())
+    }
+
+    #[test]
+    fn no_false_negatives_and_bounded_false_positives() {
+        let n = 100_000;
+        let mut bloom = BloomFilter::new(n, 0.01);
+        for i in 0..n {
+            bloom.insert(hash(i));
+        }
+
+        assert!((0..n).all(|i| bloom.contains(hash(i))));
+        let false_positives = (n..2 * n).filter(|&i| bloom.contains(hash(i))).count();
+        let rate = false_positives as f64 / n as f64;
+        assert!(rate < 0.02, "false-positive rate {}", rate);
+    }
+}
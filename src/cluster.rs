@@ -1,7 +1,10 @@
 use crate::search;
-use crate::storage::NodeHeader;
+use crate::storage::{Embeddings, NodeHeader};
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Represents a cluster in the graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +18,35 @@ pub struct Cluster {
     pub depth: usize,          // Level in hierarchy (0 = root)
 }
 
+/// Summary of a single cluster (read-only from Python)
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct ClusterStats {
+    #[pyo3(get)]
+    pub id: u64,
+    #[pyo3(get)]
+    pub size: usize,
+    /// Average pairwise similarity of members (see `calculate_cohesion`)
+    #[pyo3(get)]
+    pub cohesion: f32,
+    #[pyo3(get)]
+    pub avg_significance: f32,
+    #[pyo3(get)]
+    pub depth: usize,
+    #[pyo3(get)]
+    pub sub_cluster_count: usize,
+}
+
+#[pymethods]
+impl ClusterStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "ClusterStats(id={}, size={}, cohesion={:.3}, avg_significance={:.1}, depth={}, sub_cluster_count={})",
+            self.id, self.size, self.cohesion, self.avg_significance, self.depth, self.sub_cluster_count
+        )
+    }
+}
+
 /// Configuration for clustering algorithm
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClusterConfig {
@@ -35,6 +67,13 @@ impl Default for ClusterConfig {
     }
 }
 
+/// Upper bound on Lloyd iterations in `ClusterEngine::kmeans_cluster`
+pub const KMEANS_MAX_ITERATIONS: usize = 100;
+
+/// How far (in anchor score) a previous anchor may trail the best candidate
+/// and still be kept by `ClusterEngine::keep_prior_anchors`
+pub const STABLE_ANCHOR_MARGIN: f32 = 0.05;
+
 /// Main clustering engine
 pub struct ClusterEngine {
     config: ClusterConfig,
@@ -101,35 +140,21 @@ impl ClusterEngine {
         }
     }
 
-    /// Agglomerative Hierarchical Clustering with Average Linkage
-    /// Bottom-up approach: start with each node as its own cluster, merge closest pairs
-    pub fn agglomerative_cluster(
+    /// Merge the two most similar clusters until `k_clusters` remain, or until
+    /// no pair is at least `similarity_threshold` similar (average linkage), in
+    /// which case more than `k_clusters` are left
+    /// `progress(merges_done, merges_total)` runs after every merge; returning
+    /// false stops early, in which case this returns false
+    fn merge_until<F: FnMut(usize, usize) -> bool>(
         &self,
+        clusters: &mut Vec<Vec<u64>>,
         embeddings: &[Vec<f32>],
         k_clusters: usize,
-    ) -> Vec<Vec<u64>> {
-        let n = embeddings.len();
-        if n == 0 {
-            return vec![];
-        }
-        if n <= k_clusters {
-            return (0..n as u64).map(|id| vec![id]).collect();
-        }
+        mut progress: F,
+    ) -> bool {
+        let total = clusters.len().saturating_sub(k_clusters);
+        let mut done = 0;
 
-        // Initialize: each node is its own cluster
-        let mut clusters: Vec<Vec<u64>> = (0..n as u64).map(|id| vec![id]).collect();
-
-        // Precompute similarity matrix (only upper triangle)
-        let mut sim_matrix: Vec<Vec<f32>> = vec![vec![0.0; n]; n];
-        for i in 0..n {
-            for j in (i + 1)..n {
-                let sim = search::cosine_similarity(&embeddings[i], &embeddings[j]);
-                sim_matrix[i][j] = sim;
-                sim_matrix[j][i] = sim;
-            }
-        }
-
-        // Merge until we have k_clusters
         while clusters.len() > k_clusters {
             // Find the two most similar clusters
             let mut best_i = 0;
@@ -147,6 +172,10 @@ impl ClusterEngine {
                 }
             }
 
+            if best_sim < self.config.similarity_threshold {
+                break;
+            }
+
             // Merge clusters[best_j] into clusters[best_i]
             // Important: remove best_j first since it's higher index
             // Then best_i index is still valid
@@ -154,11 +183,113 @@ impl ClusterEngine {
             // After removing best_j, if best_i > best_j, best_i is now best_i-1
             // But since our loop ensures best_i < best_j, this is already correct
             clusters[best_i].extend(merged);
+
+            done += 1;
+            if !progress(done, total) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Dissolve clusters smaller than `min_cluster_size`
+    /// Each member of an under-sized cluster moves to the valid cluster whose
+    /// centroid is most similar to it. If no cluster is valid yet, the smallest
+    /// one merges whole into its nearest neighbor instead, until one becomes
+    /// valid or only a single cluster is left (when the total is too small)
+    pub fn rebalance(&self, mut clusters: Vec<Vec<u64>>, embeddings: &[Vec<f32>]) -> Vec<Vec<u64>> {
+        let min_size = self.config.min_cluster_size;
+        clusters.retain(|c| !c.is_empty());
+
+        while clusters.len() > 1 {
+            let (small, size) = clusters.iter()
+                .enumerate()
+                .map(|(i, c)| (i, c.len()))
+                .min_by_key(|&(_, len)| len)
+                .unwrap();
+            if size >= min_size {
+                break;
+            }
+
+            let members = clusters.swap_remove(small);
+            let centroids: Vec<Vec<f32>> = clusters.iter()
+                .map(|c| self.calculate_centroid(c, embeddings))
+                .collect();
+            let nearest = |emb: &[f32], valid_only: bool| {
+                (0..clusters.len())
+                    .filter(|&i| !valid_only || clusters[i].len() >= min_size)
+                    .max_by(|&a, &b| {
+                        search::cosine_similarity(emb, &centroids[a])
+                            .total_cmp(&search::cosine_similarity(emb, &centroids[b]))
+                    })
+            };
+
+            if clusters.iter().any(|c| c.len() >= min_size) {
+                let targets: Vec<usize> = members.iter()
+                    .map(|&id| nearest(&embeddings[id as usize], true).unwrap())
+                    .collect();
+                for (id, target) in members.into_iter().zip(targets) {
+                    clusters[target].push(id);
+                }
+            } else {
+                let centroid = self.calculate_centroid(&members, embeddings);
+                let target = nearest(&centroid, false).unwrap();
+                clusters[target].extend(members);
+            }
         }
 
         clusters
     }
 
+    /// Agglomerative Hierarchical Clustering with Average Linkage
+    /// Bottom-up approach: start with each node as its own cluster, merge closest pairs
+    pub fn agglomerative_cluster(
+        &self,
+        embeddings: &[Vec<f32>],
+        k_clusters: usize,
+    ) -> Vec<Vec<u64>> {
+        self.agglomerative_cluster_with_progress(embeddings, k_clusters, |_, _| true)
+            .unwrap_or_default()
+    }
+
+    /// Same as `agglomerative_cluster`, reporting each merge to `progress`
+    /// Returns None if `progress` asked to stop
+    pub fn agglomerative_cluster_with_progress<F: FnMut(usize, usize) -> bool>(
+        &self,
+        embeddings: &[Vec<f32>],
+        k_clusters: usize,
+        progress: F,
+    ) -> Option<Vec<Vec<u64>>> {
+        let n = embeddings.len();
+        if n == 0 {
+            return Some(vec![]);
+        }
+        if n <= k_clusters {
+            return Some((0..n as u64).map(|id| vec![id]).collect());
+        }
+
+        // Initialize: each node is its own cluster
+        let mut clusters: Vec<Vec<u64>> = (0..n as u64).map(|id| vec![id]).collect();
+
+        // Precompute similarity matrix (only upper triangle)
+        let mut sim_matrix: Vec<Vec<f32>> = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let sim = search::cosine_similarity(&embeddings[i], &embeddings[j]);
+                sim_matrix[i][j] = sim;
+                sim_matrix[j][i] = sim;
+            }
+        }
+
+        // Merge until we have k_clusters
+        if !self.merge_until(&mut clusters, embeddings, k_clusters, progress) {
+            return None;
+        }
+
+        Some(clusters)
+    }
+
     /// Find the best anchor (representative node) for a cluster
     fn find_cluster_anchor(
         &self,
@@ -178,15 +309,7 @@ impl ClusterEngine {
         let mut best_score = f32::MIN;
 
         for &id in members {
-            let sim_to_centroid = search::cosine_similarity(
-                &embeddings[id as usize],
-                &centroid,
-            );
-            let significance = headers[id as usize].significance as f32 / 9.0;
-            
-            // Combined score: closeness to centroid + significance
-            let score = sim_to_centroid * 0.7 + significance * 0.3;
-            
+            let score = self.anchor_score(id, &centroid, embeddings, headers);
             if score > best_score {
                 best_score = score;
                 best_anchor = id;
@@ -196,6 +319,63 @@ impl ClusterEngine {
         best_anchor
     }
 
+    /// How well a node represents a cluster with this centroid
+    /// Combined score: closeness to centroid + significance
+    fn anchor_score(&self, id: u64, centroid: &[f32], embeddings: &[Vec<f32>], headers: &[NodeHeader]) -> f32 {
+        let sim_to_centroid = search::cosine_similarity(&embeddings[id as usize], centroid);
+        let significance = headers[id as usize].significance as f32 / 255.0;
+        sim_to_centroid * 0.7 + significance * 0.3
+    }
+
+    /// Re-cluster from scratch but keep previously chosen anchors where they
+    /// remain good, so cluster labels don't churn across refreshes
+    pub fn recluster_stable(
+        &self,
+        embeddings: &[Vec<f32>],
+        headers: &[NodeHeader],
+        prior_clusters: &[Cluster],
+        k_clusters: usize,
+    ) -> Vec<Cluster> {
+        let mut clusters = self.cluster_graph(headers, embeddings, &[], k_clusters);
+        self.keep_prior_anchors(&mut clusters, prior_clusters, embeddings, headers);
+        clusters
+    }
+
+    /// Swap each cluster's anchor (at every level) for a member that was an
+    /// anchor in `prior_clusters`, if that member's anchor score is within
+    /// `STABLE_ANCHOR_MARGIN` of the best member's
+    pub fn keep_prior_anchors(
+        &self,
+        clusters: &mut [Cluster],
+        prior_clusters: &[Cluster],
+        embeddings: &[Vec<f32>],
+        headers: &[NodeHeader],
+    ) {
+        let prior_anchors = collect_anchor_ids(prior_clusters);
+        self.keep_anchors_in(clusters, &prior_anchors, embeddings, headers);
+    }
+
+    fn keep_anchors_in(
+        &self,
+        clusters: &mut [Cluster],
+        prior_anchors: &HashSet<u64>,
+        embeddings: &[Vec<f32>],
+        headers: &[NodeHeader],
+    ) {
+        for cluster in clusters {
+            let best = self.anchor_score(cluster.anchor_node_id, &cluster.centroid, embeddings, headers);
+            let kept = cluster.member_ids.iter()
+                .filter(|id| prior_anchors.contains(id))
+                .map(|&id| (id, self.anchor_score(id, &cluster.centroid, embeddings, headers)))
+                .filter(|&(_, score)| score >= best - STABLE_ANCHOR_MARGIN)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((id, _)) = kept {
+                cluster.anchor_node_id = id;
+            }
+            self.keep_anchors_in(&mut cluster.sub_clusters, prior_anchors, embeddings, headers);
+        }
+    }
+
     /// Build hierarchical cluster structure from flat clusters
     fn build_cluster_hierarchy(
         &self,
@@ -225,9 +405,15 @@ impl ClusterEngine {
             let sub_clusters = if members.len() > self.config.max_cluster_size 
                                   && depth < self.config.max_depth {
                 // Create filtered embeddings for sub-clustering
-                let sub_k = (members.len() / self.config.min_cluster_size).max(2).min(5);
-                let sub_flat = self.agglomerative_cluster_subset(&members, embeddings, sub_k);
-                self.build_cluster_hierarchy(sub_flat, embeddings, headers, cluster_id_counter, depth + 1)
+                let sub_k = (members.len() / self.config.min_cluster_size).clamp(2, 5);
+                let sub_flat = self.agglomerative_cluster_subset(&members, embeddings, sub_k, |_, _| true)
+                    .unwrap_or_default();
+                let sub_flat = self.rebalance(sub_flat, embeddings);
+                if sub_flat.len() > 1 {
+                    self.build_cluster_hierarchy(sub_flat, embeddings, headers, cluster_id_counter, depth + 1)
+                } else {
+                    Vec::new()
+                }
             } else {
                 Vec::new()
             };
@@ -247,42 +433,28 @@ impl ClusterEngine {
     }
 
     /// Agglomerative clustering on a subset of nodes
-    fn agglomerative_cluster_subset(
+    /// Returns None if `progress` asked to stop
+    fn agglomerative_cluster_subset<F: FnMut(usize, usize) -> bool>(
         &self,
         subset_ids: &[u64],
         embeddings: &[Vec<f32>],
         k_clusters: usize,
-    ) -> Vec<Vec<u64>> {
+        progress: F,
+    ) -> Option<Vec<Vec<u64>>> {
         let n = subset_ids.len();
         if n <= k_clusters {
-            return subset_ids.iter().map(|&id| vec![id]).collect();
+            return Some(subset_ids.iter().map(|&id| vec![id]).collect());
         }
 
         // Initialize: each node is its own cluster
         let mut clusters: Vec<Vec<u64>> = subset_ids.iter().map(|&id| vec![id]).collect();
 
         // Merge until we have k_clusters
-        while clusters.len() > k_clusters {
-            let mut best_i = 0;
-            let mut best_j = 1;
-            let mut best_sim = f32::MIN;
-
-            for i in 0..clusters.len() {
-                for j in (i + 1)..clusters.len() {
-                    let sim = self.average_linkage_distance(&clusters[i], &clusters[j], embeddings);
-                    if sim > best_sim {
-                        best_sim = sim;
-                        best_i = i;
-                        best_j = j;
-                    }
-                }
-            }
-
-            let merged = clusters.remove(best_j);
-            clusters[best_i].extend(merged);
+        if !self.merge_until(&mut clusters, embeddings, k_clusters, progress) {
+            return None;
         }
 
-        clusters
+        Some(clusters)
     }
 
     /// Main entry point: Cluster the entire graph using agglomerative clustering
@@ -290,18 +462,171 @@ impl ClusterEngine {
         &self,
         headers: &[NodeHeader],
         embeddings: &[Vec<f32>],
-        _edge_list: &[Vec<u64>],  // Kept for API compatibility, could be used for graph-aware clustering
+        edge_list: &[Vec<u64>],
         k_clusters: usize,
     ) -> Vec<Cluster> {
+        self.cluster_graph_with_progress(headers, embeddings, edge_list, k_clusters, |_, _| true)
+            .unwrap_or_default()
+    }
+
+    /// Same as `cluster_graph`, calling `progress(merges_done, merges_total)`
+    /// after each top-level merge; returning false from it cancels the run
+    /// Returns None if cancelled
+    pub fn cluster_graph_with_progress<F: FnMut(usize, usize) -> bool>(
+        &self,
+        headers: &[NodeHeader],
+        embeddings: &[Vec<f32>],
+        _edge_list: &[Vec<u64>],  // Kept for API compatibility, could be used for graph-aware clustering
+        k_clusters: usize,
+        progress: F,
+    ) -> Option<Vec<Cluster>> {
         if embeddings.is_empty() {
-            return vec![];
+            return Some(vec![]);
         }
 
-        // Step 1: Perform agglomerative clustering
-        let flat_clusters = self.agglomerative_cluster(embeddings, k_clusters);
+        // Step 1: Perform agglomerative clustering (deleted nodes keep their
+        // slots in `embeddings` but must not be clustered)
+        let live: Vec<u64> = headers.iter().filter(|h| h.is_live()).map(|h| h.id).collect();
+        let flat_clusters = if live.len() == embeddings.len() {
+            self.agglomerative_cluster_with_progress(embeddings, k_clusters, progress)?
+        } else {
+            self.agglomerative_cluster_subset(&live, embeddings, k_clusters, progress)?
+        };
+        let flat_clusters = self.rebalance(flat_clusters, embeddings);
 
         // Step 2: Build hierarchical structure with anchors and metadata
         let mut cluster_id_counter = 0u64;
+        Some(self.build_cluster_hierarchy(flat_clusters, embeddings, headers, &mut cluster_id_counter, 0))
+    }
+
+    /// Cluster the graph with the best `k` in `k_range` (inclusive)
+    /// One agglomerative pass over live nodes is snapshotted at every `k` in the
+    /// range; the flat clustering with the highest mean silhouette wins
+    /// (ties go to the smaller `k`) and is then built like `cluster_graph`
+    pub fn auto_cluster(
+        &self,
+        headers: &[NodeHeader],
+        embeddings: &[Vec<f32>],
+        k_range: (usize, usize),
+    ) -> Vec<Cluster> {
+        let (k_min, k_max) = (k_range.0.max(1), k_range.1.max(k_range.0.max(1)));
+        let live: Vec<u64> = headers.iter().filter(|h| h.is_live()).map(|h| h.id).collect();
+        if live.is_empty() {
+            return vec![];
+        }
+
+        let mut clusters: Vec<Vec<u64>> = live.iter().map(|&id| vec![id]).collect();
+        let mut best: Option<(f32, Vec<Vec<u64>>)> = None;
+        for k in (k_min..=k_max).rev() {
+            self.merge_until(&mut clusters, embeddings, k, |_, _| true);
+            if clusters.len() > k_max {
+                // The similarity threshold stopped merging above the range
+                continue;
+            }
+            let score = silhouette(&clusters, embeddings);
+            if best.as_ref().is_none_or(|(best_score, _)| score >= *best_score) {
+                best = Some((score, clusters.clone()));
+            }
+        }
+
+        let flat_clusters = best.map(|(_, c)| c).unwrap_or(clusters);
+        let flat_clusters = self.rebalance(flat_clusters, embeddings);
+        let mut cluster_id_counter = 0u64;
+        self.build_cluster_hierarchy(flat_clusters, embeddings, headers, &mut cluster_id_counter, 0)
+    }
+
+    /// K-means over `ids` with k-means++ seeding, using cosine similarity
+    /// All randomness comes from a `StdRng` seeded with `seed` and ties go to
+    /// the lowest index, so the same seed and data always give the same
+    /// clusters. Stops when assignments settle or after `KMEANS_MAX_ITERATIONS`
+    pub fn kmeans_cluster(
+        &self,
+        ids: &[u64],
+        embeddings: &[Vec<f32>],
+        k_clusters: usize,
+        seed: u64,
+    ) -> Vec<Vec<u64>> {
+        if ids.len() <= k_clusters {
+            return ids.iter().map(|&id| vec![id]).collect();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let emb = |i: usize| &embeddings[ids[i] as usize];
+
+        // k-means++: each next centroid is drawn with probability proportional
+        // to the squared cosine distance from the nearest centroid so far
+        let mut centroids = vec![emb(rng.gen_range(0..ids.len())).clone()];
+        while centroids.len() < k_clusters {
+            let weights: Vec<f32> = (0..ids.len())
+                .map(|i| {
+                    let nearest = centroids.iter()
+                        .map(|c| search::cosine_similarity(emb(i), c))
+                        .fold(f32::MIN, f32::max);
+                    (1.0 - nearest).max(0.0).powi(2)
+                })
+                .collect();
+            let total: f32 = weights.iter().sum();
+            if total <= 0.0 {
+                break; // Every point already coincides with a centroid
+            }
+            let mut target = rng.gen::<f32>() * total;
+            let mut pick = ids.len() - 1;
+            for (i, w) in weights.iter().enumerate() {
+                if target < *w {
+                    pick = i;
+                    break;
+                }
+                target -= w;
+            }
+            centroids.push(emb(pick).clone());
+        }
+
+        // Lloyd iterations
+        let mut assignment = vec![usize::MAX; ids.len()];
+        for _ in 0..KMEANS_MAX_ITERATIONS {
+            let mut changed = false;
+            for (i, slot) in assignment.iter_mut().enumerate() {
+                let mut best = (0, f32::MIN);
+                for (c, centroid) in centroids.iter().enumerate() {
+                    let sim = search::cosine_similarity(emb(i), centroid);
+                    if sim > best.1 {
+                        best = (c, sim);
+                    }
+                }
+                changed |= *slot != best.0;
+                *slot = best.0;
+            }
+            if !changed {
+                break;
+            }
+            for (c, centroid) in centroids.iter_mut().enumerate() {
+                let members: Vec<u64> = (0..ids.len()).filter(|&i| assignment[i] == c).map(|i| ids[i]).collect();
+                if !members.is_empty() {
+                    *centroid = self.calculate_centroid(&members, embeddings);
+                }
+            }
+        }
+
+        let mut clusters = vec![Vec::new(); centroids.len()];
+        for (i, &c) in assignment.iter().enumerate() {
+            clusters[c].push(ids[i]);
+        }
+        clusters.retain(|c| !c.is_empty());
+        clusters
+    }
+
+    /// Like `cluster_graph`, but the root level comes from `kmeans_cluster`
+    /// (deterministic for a given `seed`) instead of agglomerative merging
+    pub fn cluster_graph_kmeans(
+        &self,
+        headers: &[NodeHeader],
+        embeddings: &[Vec<f32>],
+        k_clusters: usize,
+        seed: u64,
+    ) -> Vec<Cluster> {
+        let live: Vec<u64> = headers.iter().filter(|h| h.is_live()).map(|h| h.id).collect();
+        let flat_clusters = self.kmeans_cluster(&live, embeddings, k_clusters, seed);
+        let flat_clusters = self.rebalance(flat_clusters, embeddings);
+        let mut cluster_id_counter = 0u64;
         self.build_cluster_hierarchy(flat_clusters, embeddings, headers, &mut cluster_id_counter, 0)
     }
 
@@ -327,39 +652,91 @@ impl ClusterEngine {
         &self,
         query_embedding: &[f32],
         cluster: &Cluster,
-        embeddings: &[Vec<f32>],
+        embeddings: &Embeddings,
         k: usize,
     ) -> Vec<(u64, f32)> {
-        let mut results: Vec<(u64, f32)> = cluster
+        let results = cluster
             .member_ids
             .iter()
             .map(|&id| {
-                let sim = search::cosine_similarity(query_embedding, &embeddings[id as usize]);
+                let sim = search::cosine_similarity(query_embedding, &embeddings.vector(id as usize));
                 (id, sim)
-            })
+            });
+
+        search::top_k(results, k)
+    }
+
+    /// The `k` clusters most similar to `cluster_id` by centroid cosine, among
+    /// the other clusters at the same depth of the hierarchy, most similar
+    /// first. Empty if `cluster_id` isn't in `clusters`.
+    pub fn cluster_neighbors(&self, cluster_id: u64, clusters: &[Cluster], k: usize) -> Vec<(u64, f32)> {
+        let Some(target) = find_cluster(clusters, cluster_id) else {
+            return Vec::new();
+        };
+
+        let mut peers = Vec::new();
+        let mut stack: Vec<&Cluster> = clusters.iter().collect();
+        while let Some(c) = stack.pop() {
+            if c.depth == target.depth {
+                if c.id != cluster_id {
+                    peers.push((c.id, search::cosine_similarity(&target.centroid, &c.centroid)));
+                }
+            } else {
+                stack.extend(c.sub_clusters.iter());
+            }
+        }
+
+        search::top_k(peers, k)
+    }
+
+    /// Count how many of `node_count` nodes belong to some cluster in the hierarchy
+    /// Returns (covered, uncovered_ids) with the uncovered ids in ascending order
+    pub fn coverage(&self, clusters: &[Cluster], node_count: usize) -> (usize, Vec<u64>) {
+        let mut covered = vec![false; node_count];
+        let mut stack: Vec<&Cluster> = clusters.iter().collect();
+
+        while let Some(c) = stack.pop() {
+            for &id in &c.member_ids {
+                if let Some(slot) = covered.get_mut(id as usize) {
+                    *slot = true;
+                }
+            }
+            stack.extend(c.sub_clusters.iter());
+        }
+
+        let uncovered: Vec<u64> = (0..node_count as u64)
+            .filter(|&id| !covered[id as usize])
             .collect();
 
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.into_iter().take(k).collect()
+        (node_count - uncovered.len(), uncovered)
+    }
+
+    /// Summarize one cluster: size, cohesion, significance and shape
+    pub fn cluster_stats(&self, cluster: &Cluster, embeddings: &Embeddings) -> ClusterStats {
+        ClusterStats {
+            id: cluster.id,
+            size: cluster.member_ids.len(),
+            cohesion: self.calculate_cohesion(cluster, embeddings),
+            avg_significance: cluster.significance,
+            depth: cluster.depth,
+            sub_cluster_count: cluster.sub_clusters.len(),
+        }
     }
 
     /// Calculate cluster cohesion (average intra-cluster similarity)
-    pub fn calculate_cohesion(&self, cluster: &Cluster, embeddings: &[Vec<f32>]) -> f32 {
+    pub fn calculate_cohesion(&self, cluster: &Cluster, embeddings: &Embeddings) -> f32 {
         if cluster.member_ids.len() <= 1 {
             return 1.0; // Single node cluster is perfectly cohesive
         }
 
         let mut total_sim = 0.0;
         let mut count = 0;
+        // Read each member once; mapped embeddings are copied on every read
+        let vectors: Vec<_> = cluster.member_ids.iter().map(|&id| embeddings.vector(id as usize)).collect();
 
-        for i in 0..cluster.member_ids.len() {
-            for j in (i + 1)..cluster.member_ids.len() {
-                let id_a = cluster.member_ids[i];
-                let id_b = cluster.member_ids[j];
-                let sim = search::cosine_similarity(
-                    &embeddings[id_a as usize],
-                    &embeddings[id_b as usize],
-                );
+        for i in 0..vectors.len() {
+            for j in (i + 1)..vectors.len() {
+                let sim = search::cosine_similarity(&vectors[i], &vectors[j]);
                 total_sim += sim;
                 count += 1;
             }
@@ -373,6 +750,45 @@ impl ClusterEngine {
     }
 }
 
+/// Utility: Mean silhouette of a flat clustering under cosine distance
+/// In [-1, 1]; higher means tighter, better-separated clusters. Members of
+/// singleton clusters count as 0, and a single cluster scores 0 overall
+pub fn silhouette(clusters: &[Vec<u64>], embeddings: &[Vec<f32>]) -> f32 {
+    let total: usize = clusters.iter().map(Vec::len).sum();
+    if clusters.len() < 2 || total == 0 {
+        return 0.0;
+    }
+
+    let mean_distance = |id: u64, members: &[u64]| {
+        let sum: f32 = members.iter()
+            .filter(|&&other| other != id)
+            .map(|&other| 1.0 - search::cosine_similarity(&embeddings[id as usize], &embeddings[other as usize]))
+            .sum();
+        let count = members.iter().filter(|&&other| other != id).count();
+        sum / count.max(1) as f32
+    };
+
+    let mut sum = 0.0;
+    for (ci, cluster) in clusters.iter().enumerate() {
+        if cluster.len() < 2 {
+            continue;
+        }
+        for &id in cluster {
+            let a = mean_distance(id, cluster);
+            let b = clusters.iter()
+                .enumerate()
+                .filter(|&(cj, c)| cj != ci && !c.is_empty())
+                .map(|(_, c)| mean_distance(id, c))
+                .fold(f32::MAX, f32::min);
+            let denom = a.max(b);
+            if denom > 0.0 {
+                sum += (b - a) / denom;
+            }
+        }
+    }
+    sum / total as f32
+}
+
 /// Utility: Export clusters for visualization
 pub fn export_cluster_tree(cluster: &Cluster) -> String {
     fn build_tree(c: &Cluster, indent: usize) -> String {
@@ -390,4 +806,222 @@ pub fn export_cluster_tree(cluster: &Cluster) -> String {
     }
     
     build_tree(cluster, 0)
-}
\ No newline at end of file
+}
+
+/// Utility: Collect the anchor node of every cluster in the hierarchy
+pub fn collect_anchor_ids(clusters: &[Cluster]) -> HashSet<u64> {
+    let mut anchors = HashSet::new();
+    let mut stack: Vec<&Cluster> = clusters.iter().collect();
+
+    while let Some(c) = stack.pop() {
+        anchors.insert(c.anchor_node_id);
+        stack.extend(c.sub_clusters.iter());
+    }
+
+    anchors
+}
+
+/// Utility: Drop removed nodes from every cluster in the hierarchy
+///
+/// A cluster whose anchor was removed falls back to its first remaining
+/// member; clusters left without members are dropped.
+pub fn remove_members(clusters: &mut Vec<Cluster>, removed: &HashSet<u64>) {
+    clusters.retain_mut(|c| {
+        c.member_ids.retain(|id| !removed.contains(id));
+        remove_members(&mut c.sub_clusters, removed);
+        if removed.contains(&c.anchor_node_id) {
+            if let Some(&first) = c.member_ids.first() {
+                c.anchor_node_id = first;
+            }
+        }
+        !c.member_ids.is_empty()
+    });
+}
+
+/// Utility: Rewrite member and anchor ids throughout the hierarchy
+///
+/// Ids missing from `id_map` are dropped, as with `remove_members`.
+pub fn remap_members(clusters: &mut Vec<Cluster>, id_map: &HashMap<u64, u64>) {
+    let removed: HashSet<u64> = clusters.iter()
+        .flat_map(|c| c.member_ids.iter().copied())
+        .filter(|id| !id_map.contains_key(id))
+        .collect();
+    remove_members(clusters, &removed);
+    fn rewrite(clusters: &mut [Cluster], id_map: &HashMap<u64, u64>) {
+        for c in clusters {
+            for id in &mut c.member_ids {
+                *id = id_map[id];
+            }
+            c.anchor_node_id = id_map[&c.anchor_node_id];
+            rewrite(&mut c.sub_clusters, id_map);
+        }
+    }
+    rewrite(clusters, id_map);
+}
+
+/// Utility: Find a cluster by id anywhere in the hierarchy
+pub fn find_cluster(clusters: &[Cluster], cluster_id: u64) -> Option<&Cluster> {
+    clusters.iter().find_map(|c| {
+        if c.id == cluster_id {
+            Some(c)
+        } else {
+            find_cluster(&c.sub_clusters, cluster_id)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(n: usize) -> Vec<NodeHeader> {
+        (0..n as u64)
+            .map(|id| NodeHeader {
+                id, data_offset: 0, data_len: 0, edge_start: 0, edge_count: 0,
+                last_access_ts: 0, access_count: 0, recent_access: 0.0,
+                significance: 128, flags: 0, created_ts: 0, ttl_secs: 0,
+            })
+            .collect()
+    }
+
+    /// `per_group` points tightly spread around each of the given directions.
+    fn groups(directions: &[[f32; 3]], per_group: usize) -> Vec<Vec<f32>> {
+        directions.iter()
+            .flat_map(|d| (0..per_group).map(move |i| {
+                let jitter = i as f32 * 0.02;
+                vec![d[0] + jitter, d[1] + jitter * 0.5, d[2] - jitter * 0.3]
+            }))
+            .collect()
+    }
+
+    fn two_groups() -> Vec<Vec<f32>> {
+        groups(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], 5)
+    }
+
+    #[test]
+    fn coverage_is_full_when_every_node_clusters() {
+        let embeddings = two_groups();
+        let engine = ClusterEngine::new(ClusterConfig::default());
+        let clusters = engine.cluster_graph(&headers(10), &embeddings, &[], 2);
+        assert_eq!(engine.coverage(&clusters, 10), (10, vec![]));
+
+        let mut partial = clusters.clone();
+        remove_members(&mut partial, &HashSet::from([3, 7]));
+        assert_eq!(engine.coverage(&partial, 10), (8, vec![3, 7]));
+    }
+
+    #[test]
+    fn cluster_stats_match_their_sources() {
+        let embeddings = two_groups();
+        let engine = ClusterEngine::new(ClusterConfig::default());
+        let clusters = engine.cluster_graph(&headers(10), &embeddings, &[], 2);
+        let embeddings = embeddings.into();
+
+        for cluster in &clusters {
+            let stats = engine.cluster_stats(cluster, &embeddings);
+            assert_eq!(stats.size, cluster.member_ids.len());
+            assert_eq!(stats.cohesion, engine.calculate_cohesion(cluster, &embeddings));
+            assert_eq!(stats.sub_cluster_count, cluster.sub_clusters.len());
+        }
+    }
+
+    #[test]
+    fn rebalance_leaves_no_undersized_cluster() {
+        let embeddings = groups(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], 4);
+        let engine = ClusterEngine::new(ClusterConfig::default());
+        let min_size = engine.config.min_cluster_size;
+
+        // A singleton split off the third group
+        let split = vec![(0..4).collect(), (4..8).collect(), vec![8], vec![9, 10, 11]];
+        let rebalanced = engine.rebalance(split, &embeddings);
+        assert!(rebalanced.iter().all(|c| c.len() >= min_size), "{:?}", rebalanced);
+        let mut members: Vec<u64> = rebalanced.concat();
+        members.sort();
+        assert_eq!(members, (0..12).collect::<Vec<_>>());
+
+        // Too few nodes for two valid clusters: everything ends up together
+        let rebalanced = engine.rebalance(vec![vec![0], vec![4]], &embeddings);
+        assert_eq!(rebalanced.len(), 1);
+    }
+
+    #[test]
+    fn raising_similarity_threshold_stops_merges_earlier() {
+        let embeddings = groups(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], 4);
+        let count = |threshold: f32| {
+            let config = ClusterConfig { similarity_threshold: threshold, ..ClusterConfig::default() };
+            ClusterEngine::new(config).agglomerative_cluster(&embeddings, 1).len()
+        };
+
+        // Unrelated groups only merge when the floor allows it
+        assert_eq!(count(-1.0), 1);
+        assert_eq!(count(0.5), 3);
+        assert!(count(0.99999) > 3);
+    }
+
+    #[test]
+    fn recluster_keeps_an_unchanged_prior_anchor() {
+        let mut embeddings = two_groups();
+        let engine = ClusterEngine::new(ClusterConfig::default());
+        let prior = engine.cluster_graph(&headers(10), &embeddings, &[], 2);
+        let prior_anchors = collect_anchor_ids(&prior);
+
+        // A slightly more significant twin of the first group's anchor
+        // out-scores it, but only by less than the stability margin
+        let group = prior.iter().find(|c| c.member_ids.contains(&0)).unwrap();
+        embeddings.push(embeddings[group.anchor_node_id as usize].clone());
+        let mut grown = headers(11);
+        grown[10].significance = 150;
+        let fresh = engine.cluster_graph(&grown, &embeddings, &[], 2);
+        assert!(collect_anchor_ids(&fresh).contains(&10));
+
+        let stable = engine.recluster_stable(&embeddings, &grown, &prior, 2);
+        assert_eq!(collect_anchor_ids(&stable), prior_anchors);
+    }
+
+    #[test]
+    fn auto_cluster_finds_two_groups() {
+        let embeddings = groups(&[[1.0, 0.2, 0.0], [0.0, 0.3, 1.0]], 8);
+        // No merge floor, so the silhouette alone has to pick k
+        let config = ClusterConfig { similarity_threshold: -1.0, ..ClusterConfig::default() };
+        let engine = ClusterEngine::new(config);
+
+        let clusters = engine.auto_cluster(&headers(16), &embeddings, (2, 8));
+        assert_eq!(clusters.len(), 2);
+        let mut split: Vec<Vec<u64>> = clusters.iter()
+            .map(|c| { let mut m = c.member_ids.clone(); m.sort(); m })
+            .collect();
+        split.sort();
+        assert_eq!(split, vec![(0..8).collect::<Vec<_>>(), (8..16).collect()]);
+    }
+
+    #[test]
+    fn kmeans_with_the_same_seed_is_reproducible() {
+        // Many loosely spread points, so seeding choices actually matter
+        let embeddings: Vec<Vec<f32>> = (0..40)
+            .map(|i| { let a = i as f32 * 0.37; vec![a.cos(), a.sin(), (a * 0.5).sin()] })
+            .collect();
+        let ids: Vec<u64> = (0..40).collect();
+        let engine = ClusterEngine::new(ClusterConfig::default());
+
+        let first = engine.kmeans_cluster(&ids, &embeddings, 4, 42);
+        assert_eq!(first.len(), 4);
+        assert_eq!(engine.kmeans_cluster(&ids, &embeddings, 4, 42), first);
+    }
+
+    #[test]
+    fn cluster_neighbors_rank_peers_by_centroid_similarity() {
+        // a and b are 45 degrees apart, c is orthogonal to both
+        let embeddings = groups(&[[1.0, 0.0, 0.0], [0.7, 0.7, 0.0], [0.0, 0.0, 1.0]], 5);
+        let engine = ClusterEngine::new(ClusterConfig::default());
+        let clusters = engine.cluster_graph(&headers(15), &embeddings, &[], 3);
+        assert_eq!(clusters.len(), 3);
+        let id_of = |node: u64| clusters.iter().find(|c| c.member_ids.contains(&node)).unwrap().id;
+        let (a, b, c) = (id_of(0), id_of(5), id_of(10));
+
+        let neighbors = engine.cluster_neighbors(a, &clusters, 5);
+        assert_eq!(neighbors.iter().map(|&(id, _)| id).collect::<Vec<_>>(), vec![b, c]);
+        assert!(neighbors[0].1 > 0.6 && neighbors[1].1 < 0.1, "{:?}", neighbors);
+        assert_eq!(engine.cluster_neighbors(c, &clusters, 1).len(), 1);
+        assert!(engine.cluster_neighbors(u64::MAX, &clusters, 5).is_empty());
+    }
+}
@@ -1,45 +1,312 @@
 use crate::bio;
+use crate::bloom::BloomFilter;
+use crate::error::SpiderError;
+use crate::graph;
 use crate::search;
-use crate::storage::NodeHeader;
-use crate::cluster::{ClusterEngine, ClusterConfig, Cluster};
+use crate::storage::{self, NodeHeader};
+use crate::cluster::{ClusterEngine, ClusterConfig, Cluster, ClusterStats};
+use crate::query::{SearchQuery, SearchResults};
 use crate::ranking;
 use pyo3::prelude::*;
-use std::time::{SystemTime, UNIX_EPOCH};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use serde::{Serialize, Deserialize};
 use std::fs::File;
 use std::path::Path;
-use std::io::{BufReader, BufWriter};
+use std::sync::{Arc, Mutex};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+/// Default number of inflated contents kept by the content cache
+const DEFAULT_CONTENT_CACHE_CAPACITY: usize = 64;
+
+/// Nodes scanned per GIL release in `exact_search`; signals are checked between chunks.
+const EXACT_SEARCH_CHUNK: usize = 16_384;
+
+/// Nodes as (id, label, significance, cluster_id) and edges as (source, target).
+type GraphExport = (Vec<(u64, String, u8, Option<u64>)>, Vec<(u64, u64)>);
+
+/// First bytes of a snapshot file, followed by `SNAPSHOT_VERSION` as a
+/// little-endian u32 and then the bincode-encoded `SpiderSnapshot`. Files
+/// without the magic are format version 0 (`SnapshotV0`) and are migrated
+/// to the current layout on load.
+const SNAPSHOT_MAGIC: &[u8; 4] = b"SPDB";
+/// Bump whenever `SpiderSnapshot`'s layout changes, keeping a struct for the
+/// old layout so existing files still load.
+const SNAPSHOT_VERSION: u32 = 1;
 
 // SNAPSHOT: Stores data, BUT NOT the Index (we rebuild it)
+// `H` is the heap: owned, or borrowed from the file bytes so `open_mmap` can
+// find where it sits in the mapping. `E` is the embeddings: owned, or just
+// their lengths (`storage::SkippedVector`) when `open_mmap` reads them from
+// the mapping.
 #[derive(Serialize, Deserialize)]
-struct SpiderSnapshot {
+struct SpiderSnapshot<H = Vec<u8>, E = Vec<f32>> {
     headers: Vec<NodeHeader>,
-    data_heap: Vec<u8>,
+    data_heap: H,
+    edge_list: Vec<Vec<u64>>,
+    edge_created: Vec<Vec<u64>>,
+    embeddings: Vec<E>,
+    clusters: Option<Vec<Cluster>>,
+    cluster_config: ClusterConfig,
+}
+
+/// Snapshot layout of format version 0, before per-edge creation times and
+/// the header fields listed on `storage::NodeHeaderV0`.
+#[derive(Deserialize)]
+struct SnapshotV0<H, E = Vec<f32>> {
+    headers: Vec<storage::NodeHeaderV0>,
+    data_heap: H,
     edge_list: Vec<Vec<u64>>,
-    embeddings: Vec<Vec<f32>>,
+    embeddings: Vec<E>,
     clusters: Option<Vec<Cluster>>,
     cluster_config: ClusterConfig,
 }
 
+impl<H, E> From<SnapshotV0<H, E>> for SpiderSnapshot<H, E> {
+    /// Edges get the later creation time of their two endpoints, the
+    /// earliest they can have been added.
+    fn from(old: SnapshotV0<H, E>) -> Self {
+        let headers: Vec<NodeHeader> = old.headers.into_iter().map(NodeHeader::from).collect();
+        let created = |id: u64| headers.get(id as usize).map_or(0, |h| h.created_ts);
+        let edge_created = old.edge_list.iter()
+            .enumerate()
+            .map(|(source, edges)| {
+                edges.iter().map(|&target| created(source as u64).max(created(target))).collect()
+            })
+            .collect();
+
+        SpiderSnapshot {
+            headers,
+            data_heap: old.data_heap,
+            edge_list: old.edge_list,
+            edge_created,
+            embeddings: old.embeddings,
+            clusters: old.clusters,
+            cluster_config: old.cluster_config,
+        }
+    }
+}
+
+impl<E> SpiderSnapshot<&[u8], E> {
+    /// Swaps the heap, borrowed from the mapped `file`, for its byte range in
+    /// that file (see `open_mmap`).
+    fn detach_heap(self, file: &[u8]) -> (std::ops::Range<usize>, SpiderSnapshot<Vec<u8>, E>) {
+        let start = self.data_heap.as_ptr() as usize - file.as_ptr() as usize;
+        let snapshot = SpiderSnapshot {
+            headers: self.headers,
+            data_heap: Vec::new(),
+            edge_list: self.edge_list,
+            edge_created: self.edge_created,
+            embeddings: self.embeddings,
+            clusters: self.clusters,
+            cluster_config: self.cluster_config,
+        };
+        (start..start + self.data_heap.len(), snapshot)
+    }
+}
+
+/// Decodes the snapshot body of a mapped file, borrowing its heap from `body`
+/// and migrating older format versions.
+fn decode_mapped<E: serde::de::DeserializeOwned>(body: &[u8], version: u32) -> PyResult<SpiderSnapshot<&[u8], E>> {
+    match version {
+        0 => bincode::deserialize::<SnapshotV0<&[u8], E>>(body).map(SpiderSnapshot::from),
+        _ => bincode::deserialize(body),
+    }
+    .map_err(load_err)
+}
+
+fn load_err(msg: impl std::fmt::Display) -> PyErr {
+    pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to load DB: {}", msg))
+}
+
+/// Offset of the bincode body in a snapshot file starting with `prefix`
+/// (its first 8 bytes) and the file's format version: past the
+/// magic/version header, or (0, 0) for headerless files. Rejects versions
+/// newer than `SNAPSHOT_VERSION`.
+fn snapshot_body_offset(prefix: &[u8]) -> PyResult<(usize, u32)> {
+    if !prefix.starts_with(SNAPSHOT_MAGIC) {
+        return Ok((0, 0));
+    }
+    let version = prefix.get(4..8)
+        .ok_or_else(|| load_err("truncated format header"))?;
+    let version = u32::from_le_bytes(version.try_into().unwrap());
+    if version > SNAPSHOT_VERSION {
+        return Err(load_err(format!(
+            "format version {} is newer than this build supports ({})", version, SNAPSHOT_VERSION
+        )));
+    }
+    Ok((SNAPSHOT_MAGIC.len() + 4, version))
+}
+
+/// Reads a snapshot written by `save`, with or without the magic/version
+/// header, migrating older formats. Raises RuntimeError for corrupt files or
+/// ones written by a newer format version.
+fn read_snapshot(path: &str) -> PyResult<SpiderSnapshot> {
+    let file = File::open(path).map_err(SpiderError::Io)?;
+    let mut reader = BufReader::new(file);
+
+    let mut prefix = Vec::with_capacity(8);
+    (&mut reader).take(8).read_to_end(&mut prefix).map_err(SpiderError::Io)?;
+    let (body, version) = snapshot_body_offset(&prefix)?;
+    reader.seek(SeekFrom::Start(body as u64)).map_err(SpiderError::Io)?;
+
+    match version {
+        0 => bincode::deserialize_from::<_, SnapshotV0<Vec<u8>>>(reader).map(SpiderSnapshot::from),
+        _ => bincode::deserialize_from(reader),
+    }
+    .map_err(load_err)
+}
+
+/// One line of the JSON Lines export: a node plus its adjacency.
+#[derive(Serialize, Deserialize)]
+struct JsonlNode {
+    id: u64,
+    content: String,
+    embedding: Vec<f32>,
+    significance: u8,
+    access_count: u32,
+    last_access_ts: u64,
+    recent_access: f32,
+    #[serde(default)]
+    created_ts: u64,
+    #[serde(default)]
+    ttl_secs: u32,
+    neighbors: Vec<u64>,
+    /// Creation time of each edge in `neighbors`
+    #[serde(default)]
+    edge_created: Vec<u64>,
+}
+
+/// A node's content and metrics in one read-only snapshot (see `fetch`)
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct NodeRecord {
+    #[pyo3(get)]
+    pub id: u64,
+    #[pyo3(get)]
+    pub content: String,
+    /// Only filled when requested, since it's the bulk of the record
+    #[pyo3(get)]
+    pub embedding: Option<Vec<f32>>,
+    #[pyo3(get)]
+    pub significance: u8,
+    #[pyo3(get)]
+    pub access_count: u32,
+    #[pyo3(get)]
+    pub last_access_ts: u64,
+    #[pyo3(get)]
+    pub recent_access: f32,
+    #[pyo3(get)]
+    pub life_score: f32,
+}
+
+#[pymethods]
+impl NodeRecord {
+    fn __repr__(&self) -> String {
+        format!(
+            "NodeRecord(id={}, significance={}, access_count={}, last_access_ts={}, life_score={:.3})",
+            self.id, self.significance, self.access_count, self.last_access_ts, self.life_score
+        )
+    }
+}
+
+/// Changes from one database to another (see `SpiderDB::diff`).
+///
+/// Nodes are matched by content hash, so ids in `added`, `significance_changed`
+/// and `edges_added` refer to the newer database and ids in `removed` and
+/// `edges_removed` to the older one. Edges are undirected, lower id first.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct DbDiff {
+    #[pyo3(get)]
+    pub added: Vec<u64>,
+    #[pyo3(get)]
+    pub removed: Vec<u64>,
+    /// (id, old significance, new significance)
+    #[pyo3(get)]
+    pub significance_changed: Vec<(u64, u8, u8)>,
+    #[pyo3(get)]
+    pub edges_added: Vec<(u64, u64)>,
+    #[pyo3(get)]
+    pub edges_removed: Vec<(u64, u64)>,
+}
+
+#[pymethods]
+impl DbDiff {
+    fn __repr__(&self) -> String {
+        format!(
+            "DbDiff(added={}, removed={}, significance_changed={}, edges_added={}, edges_removed={})",
+            self.added.len(), self.removed.len(), self.significance_changed.len(),
+            self.edges_added.len(), self.edges_removed.len()
+        )
+    }
+}
+
 /// The main database struct holding all data arenas.
 #[pyclass]
 pub struct SpiderDB {
     /// Fixed-size metadata headers.
     headers: Vec<NodeHeader>,
-    /// Variable-length content storage.
-    data_heap: Vec<u8>,
+    /// Live access metrics, parallel to `headers`. These are the source of
+    /// truth for access_count/last_access_ts/recent_access at runtime; the
+    /// header copies are only refreshed when saving.
+    access: Vec<bio::AccessCounters>,
+    /// Variable-length content storage (in memory, or mapped by `open_mmap`).
+    data_heap: storage::Heap,
     /// Adjacency list (Vector of Vectors) for easy updates
     edge_list: Vec<Vec<u64>>,
-    /// Vector embeddings for nodes.
-    embeddings: Vec<Vec<f32>>,
+    /// Creation time (Unix seconds) of each edge, parallel to `edge_list`.
+    edge_created: Vec<Vec<u64>>,
+    /// Vector embeddings for nodes; may be served from the file (see `open_mmap`).
+    embeddings: storage::Embeddings,
     /// HNSW Index for fast approximate nearest neighbor search.
     index: search::VectorIndex,
     /// Cached cluster hierarchy
     clusters: Option<Vec<Cluster>>,
     /// Clustering configuration
     cluster_config: ClusterConfig,
+    /// Nodes inserted since the hierarchy was last built (see `maybe_recluster`).
+    /// Not persisted: starts at 0 after `load` / `open_mmap`.
+    inserted_since_cluster: usize,
+    /// Weights and bounds used by hybrid_search
+    rank_config: ranking::RankConfig,
+    /// How `add_edge` treats an edge from a node to itself
+    self_loops: graph::SelfLoops,
+    /// What hybrid search does with a zero or wrong-dimension query
+    invalid_queries: search::InvalidQueryMode,
+    /// Life score constants (validated, see `bio::BioConfig::new`)
+    bio_config: bio::BioConfig,
+    /// Thresholds for descending the cluster hierarchy during candidate selection
+    candidate_config: ranking::CandidateConfig,
     /// Path to the database file.
     file_path: Option<String>,
+    /// Content hash -> node ids with that hash (rebuilt on load, not persisted)
+    content_index: HashMap<u64, Vec<u64>>,
+    /// Optional probabilistic pre-check in front of `content_index`
+    content_bloom: Option<BloomFilter>,
+    /// Content longer than this many bytes is stored compressed (None = never)
+    compression_threshold: Option<usize>,
+    /// Recently inflated content of compressed nodes (not persisted)
+    content_cache: Mutex<storage::ContentCache>,
+    /// Grow `data_heap` in fixed steps of this many bytes instead of doubling
+    heap_growth_chunk: Option<usize>,
+    /// Heap offset below which `compact_step` has already packed live content
+    compact_cursor: usize,
+    /// Dead bytes squeezed out so far in the current compaction pass
+    compact_closed: usize,
+    /// Age-based policy applied by both vacuum and search
+    retention: Option<bio::RetentionPolicy>,
+    /// Order in which `trim_to_capacity` evicts nodes
+    eviction_policy: bio::EvictionPolicy,
+    /// Named embedding slices searchable with `search_subspace` (not persisted)
+    subspaces: HashMap<String, std::ops::Range<usize>>,
+    /// Significance assigned when add_node is called without one
+    default_significance: u8,
+    /// Logical time (Unix seconds) used instead of the wall clock when set
+    logical_clock: Option<u64>,
     
     // ========================================================================
     // AUTO-NOTIFY CONFIGURATION
@@ -64,94 +331,83 @@ impl SpiderDB {
         db_path: Option<String>,
         max_capacity: Option<usize>,
         m: Option<usize>,
-        ef_construction: Option<usize>
+        ef_construction: Option<usize>,
+        default_significance: Option<u8>
     ) -> PyResult<Self> {
         let db_path = db_path.unwrap_or("./spider.db".to_string());
+        let default_significance = default_significance.unwrap_or(storage::DEFAULT_SIGNIFICANCE);
         
         // --- 1. TRY LOADING FROM DISK ---
         if Path::new(&db_path).exists() {
             // Load Data
-            let file = File::open(&db_path).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-            let reader = BufReader::new(file);
-            let snapshot: SpiderSnapshot = bincode::deserialize_from(reader)
-                .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to load DB: {}", e)))?;
-
-            // REBUILD INDEX (The Fix)
-            // We create a fresh index and re-insert all vectors.
-            let index = search::VectorIndex::new(m, max_capacity, ef_construction);
-            for (i, vec) in snapshot.embeddings.iter().enumerate() {
-                index.add(i as u64, vec);
-            }
-
-            return Ok(SpiderDB {
-                headers: snapshot.headers,
-                data_heap: snapshot.data_heap,
-                edge_list: snapshot.edge_list,
-                embeddings: snapshot.embeddings,
-                index, // Rebuilt index
-                file_path: Some(db_path),
-                clusters: snapshot.clusters,
-                cluster_config: snapshot.cluster_config,
-                server_url: None,           // No server by default
-                auto_notify: true,           // Auto-notify enabled by default
-            });
+            let mut snapshot = read_snapshot(&db_path)?;
+            let data_heap = storage::Heap::Owned(std::mem::take(&mut snapshot.data_heap));
+            let embeddings = storage::Embeddings::Owned(std::mem::take(&mut snapshot.embeddings));
+            return Ok(Self::from_snapshot(
+                snapshot, data_heap, embeddings, db_path, max_capacity, m, ef_construction, default_significance,
+            ));
         }
 
         // --- 2. START FRESH (RAM or New File) ---
         let cap = max_capacity.unwrap_or(1_000_000);
         Ok(SpiderDB {
             headers: Vec::with_capacity(cap),
-            data_heap: Vec::with_capacity(cap * 100),
+            access: Vec::with_capacity(cap),
+            data_heap: storage::Heap::Owned(Vec::with_capacity(cap * 100)),
             edge_list: Vec::with_capacity(cap),
-            embeddings: Vec::with_capacity(cap),
+            edge_created: Vec::with_capacity(cap),
+            embeddings: storage::Embeddings::Owned(Vec::with_capacity(cap)),
             index: search::VectorIndex::new(m, max_capacity, ef_construction),
             file_path: Some(db_path), // Remember the path (even if it doesn't exist yet)
+            content_index: HashMap::new(),
+            content_bloom: None,
+            compression_threshold: None,
+            content_cache: Mutex::new(storage::ContentCache::new(DEFAULT_CONTENT_CACHE_CAPACITY)),
+            heap_growth_chunk: None,
+            compact_cursor: 0,
+            compact_closed: 0,
+            inserted_since_cluster: 0,
+            retention: None,
+            eviction_policy: bio::EvictionPolicy::LifeScore,
+            subspaces: HashMap::new(),
+            default_significance,
+            logical_clock: None,
             clusters: None,
             cluster_config: ClusterConfig::default(),
+            rank_config: ranking::RankConfig::default(),
+            self_loops: graph::SelfLoops::Drop,
+            invalid_queries: search::InvalidQueryMode::Raise,
+            bio_config: bio::BioConfig::default(),
+            candidate_config: ranking::CandidateConfig::default(),
             server_url: None,           // No server by default
             auto_notify: true,           // Auto-notify enabled by default
         })
     }
 
     /// Adds a new node and AUTOMATICALLY links (bi-directional) it to relevant existing nodes.
+    ///
+    /// `significance` is on a 0-255 scale, rounded to the nearest step; when
+    /// omitted the database's `default_significance` is used. With
+    /// `significance_scale` it is read on a 0..=`significance_scale` scale
+    /// instead (e.g. 9 for 0-9, so 4.5 is allowed) and converted (see
+    /// `significance_from_scale`). Values outside the scale raise ValueError.
+    /// With `ttl_secs` the node expires that many seconds after creation (see `expire`).
     pub fn add_node(
         &mut self, 
         content: String, 
         embedding: Vec<f32>, 
-        significance: u8, 
-        auto_link_threshold: Option<f32>
-    ) -> u64 {
-        let id = self.headers.len() as u64;
-        let data_bytes = content.as_bytes();
-        let data_offset = self.data_heap.len() as u64;
-        let data_len = data_bytes.len() as u32;
-
-        self.data_heap.extend_from_slice(data_bytes);
-        
-        // Add to HNSW Index
-        self.index.add(id, &embedding);
-        
-        // Keep raw embeddings for now
-        self.embeddings.push(embedding.clone());
-
-        // Initialize empty edge list for this new node
-        self.edge_list.push(Vec::new());
-
-        let header = NodeHeader {
-            id,
-            data_offset,
-            data_len,
-            edge_start: 0,
-            edge_count: 0,
-            last_access_ts: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            access_count: 0,
-            significance,
+        significance: Option<f32>, 
+        auto_link_threshold: Option<f32>,
+        ttl_secs: Option<u32>,
+        significance_scale: Option<f32>,
+    ) -> PyResult<u64> {
+        self.check_dimension(&embedding)?;
+        let significance = match significance {
+            Some(value) => storage::significance_from_scale(value, significance_scale.unwrap_or(255.0))?,
+            None => self.default_significance,
         };
-
-        self.headers.push(header);
+        let id = self.insert_node(content.as_bytes(), embedding, significance);
+        self.headers[id as usize].ttl_secs = ttl_secs.unwrap_or(0);
 
         // --- AUTO-LINKING LOGIC ---
         let threshold = auto_link_threshold.unwrap_or(0.6);
@@ -159,41 +415,527 @@ impl SpiderDB {
         let max_neighbors = 5;
 
         // Search existing nodes (k=10 to ensure we find valid candidates)
-        let similar_nodes = self.index.search(&embedding, max_neighbors + 1, Some(64)); 
+        let similar_nodes = self.nearest(&self.embeddings.vector(id as usize), max_neighbors + 1, Some(64));
 
         let mut edges_to_add = Vec::new();
 
         for (neighbor_id, similarity) in similar_nodes {
-            // Don't link to self, and only link if similarity is strong enough
-            if neighbor_id != id && similarity >= threshold {
+            // Don't link to self or deleted nodes, and only link if similarity is strong enough
+            if neighbor_id != id && similarity >= threshold && self.header(neighbor_id).is_ok() {
                 edges_to_add.push(neighbor_id);
             }
         }
 
         // Apply edges
         for neighbor_id in edges_to_add {
-            self.add_edge(id, neighbor_id);
+            self.add_edge(id, neighbor_id)?;
         }
 
-        id
+        Ok(id)
+    }
+
+    /// Replaces a node's content and embedding, keeping its id, edges and
+    /// significance. Returns false for unknown or deleted ids.
+    ///
+    /// The new content is appended to the heap and the old bytes become dead
+    /// space for `compact`/`compact_step` to reclaim. Counts as a touch:
+    /// `last_access_ts` moves to now (without counting an access). Changing
+    /// the embedding inserts the new vector into the HNSW index under the
+    /// same id, about the cost of one `add_node`. The old vector lingers in
+    /// the index until it is rebuilt, which happens once there are as many
+    /// superseded vectors as nodes, so a loop of n updates costs a single
+    /// rebuild rather than one each; until then every search fetches that
+    /// many extra candidates. Raises ValueError if the embedding's dimension
+    /// differs from the stored ones.
+    pub fn update_node(&mut self, id: u64, content: String, embedding: Vec<f32>) -> PyResult<bool> {
+        if self.header(id).is_err() {
+            return Ok(false);
+        }
+        self.check_dimension(&embedding)?;
+
+        self.unindex_content(id);
+        let (data_offset, data_len, flags) = self.append_content(content.as_bytes());
+        self.index_content(id, content.as_bytes());
+        self.content_cache.get_mut().unwrap_or_else(|e| e.into_inner()).invalidate(id);
+
+        let header = &mut self.headers[id as usize];
+        header.data_offset = data_offset;
+        header.data_len = data_len;
+        header.flags = (header.flags & !storage::FLAG_COMPRESSED) | flags;
+        self.access[id as usize].touch(self.now(), &self.bio_config);
+
+        if *self.embeddings.vector(id as usize) != embedding[..] {
+            self.load_embeddings();
+            let embeddings = self.embeddings.to_mut();
+            embeddings[id as usize] = embedding;
+            if self.index.is_full() {
+                self.index.grow(embeddings);
+            } else if self.index.stale() >= self.index.len() {
+                // Searches over-fetch by the stale count; rebuilding once it
+                // reaches the node count keeps updates amortized O(log n)
+                self.index.rebuild(embeddings);
+            } else {
+                self.index.replace(id, &embeddings[id as usize]);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Replaces every stored embedding with `embed_fn(content)`, e.g. after
+    /// switching embedding models, then rebuilds the HNSW index once.
+    ///
+    /// All new embeddings must share one dimension (which may differ from the
+    /// old one); otherwise ValueError is raised and nothing changes. Deleted
+    /// nodes still in the heap are re-embedded too so the index stays uniform
+    /// (`compact` first to skip them). The cluster hierarchy was built in the
+    /// old space, so it is dropped; call `build_clusters` again.
+    /// Returns the number of nodes re-embedded.
+    pub fn reembed_all(&mut self, py: Python<'_>, embed_fn: PyObject) -> PyResult<usize> {
+        let mut embeddings = Vec::with_capacity(self.headers.len());
+        for id in 0..self.headers.len() as u64 {
+            let bytes = self.content(id).ok_or(SpiderError::Truncated(id))?;
+            let text = String::from_utf8_lossy(&bytes).into_owned();
+            let embedding: Vec<f32> = embed_fn.call1(py, (text,))?.extract(py)?;
+            if embedding.is_empty() {
+                return Err(SpiderError::EmptyEmbedding.into());
+            }
+            if let Some(first) = embeddings.first().map(Vec::len) {
+                if embedding.len() != first {
+                    return Err(SpiderError::DimensionMismatch { expected: first, got: embedding.len() }.into());
+                }
+            }
+            embeddings.push(embedding);
+        }
+
+        let count = embeddings.len();
+        self.index.rebuild(&embeddings);
+        self.embeddings = embeddings.into();
+        self.clusters = None;
+        Ok(count)
+    }
+
+    /// `add_node` for f64 embeddings (e.g. numpy float64 arrays). Each
+    /// component is rounded to the nearest f32, which keeps ~7 significant
+    /// digits; storage and search are f32 as for every other node.
+    pub fn add_node_f64(
+        &mut self,
+        content: String,
+        embedding: Vec<f64>,
+        significance: Option<f32>,
+        auto_link_threshold: Option<f32>,
+        ttl_secs: Option<u32>,
+        significance_scale: Option<f32>,
+    ) -> PyResult<u64> {
+        let embedding = embedding.into_iter().map(|x| x as f32).collect();
+        self.add_node(content, embedding, significance, auto_link_threshold, ttl_secs, significance_scale)
+    }
+
+    /// Returns the id of the node with exactly this content, adding it if absent.
+    ///
+    /// Returns (id, created) where `created` is false when an existing node was found.
+    /// The lookup uses the content-hash index, so this is cheap to call for every
+    /// record in an idempotent ingestion pipeline.
+    pub fn get_or_create(
+        &mut self,
+        content: String,
+        embedding: Vec<f32>,
+        significance: Option<u8>,
+    ) -> PyResult<(u64, bool)> {
+        if let Some(id) = self.find_by_content(content.as_bytes()) {
+            return Ok((id, false));
+        }
+        Ok((self.add_node(content, embedding, significance.map(f32::from), None, None, None)?, true))
+    }
+
+    /// Returns true if a node with exactly this content exists.
+    pub fn contains(&self, content: String) -> bool {
+        self.find_by_content(content.as_bytes()).is_some()
+    }
+
+    /// Puts a Bloom filter in front of content lookups (`contains`, `get_or_create`).
+    ///
+    /// Misses are answered without touching the exact content index; hits are
+    /// still confirmed against it, so results never change, only lookup cost.
+    /// `false_positive_rate` defaults to 1%.
+    pub fn enable_content_bloom(
+        &mut self,
+        expected_items: usize,
+        false_positive_rate: Option<f64>,
+    ) -> PyResult<()> {
+        let rate = false_positive_rate.unwrap_or(0.01);
+        if !(rate > 0.0 && rate < 1.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "false_positive_rate must be in (0, 1)",
+            ));
+        }
+
+        let mut bloom = BloomFilter::new(expected_items.max(self.content_index.len()), rate);
+        for &hash in self.content_index.keys() {
+            bloom.insert(hash);
+        }
+        self.content_bloom = Some(bloom);
+        Ok(())
+    }
+
+    /// Compresses content of nodes added from now on when it exceeds `threshold_bytes`.
+    ///
+    /// Small nodes stay uncompressed to avoid overhead; reads decompress
+    /// transparently. Pass None to disable.
+    pub fn set_compression_threshold(&mut self, threshold_bytes: Option<usize>) {
+        self.compression_threshold = threshold_bytes;
+    }
+
+    /// Sets how many inflated contents of compressed nodes are kept for
+    /// repeated reads (default 64; 0 disables the cache). Clears the cache.
+    pub fn set_content_cache_capacity(&mut self, capacity: usize) {
+        self.content_cache.get_mut().unwrap_or_else(|e| e.into_inner()).resize(capacity);
+    }
+
+    /// (hits, misses) of the content cache; every miss is one decompression.
+    pub fn content_cache_stats(&self) -> (u64, u64) {
+        let cache = self.content_cache.lock().unwrap_or_else(|e| e.into_inner());
+        (cache.hits, cache.misses)
+    }
+
+    /// Grows the content heap in fixed `chunk_bytes` steps instead of `Vec` doubling.
+    ///
+    /// Doubling a multi-GB heap briefly needs ~3x its size (old buffer + 2x new)
+    /// and leaves up to 1x as slack. Chunked growth caps that at ~2x + one chunk,
+    /// at the cost of more frequent reallocations. Pass None for default doubling.
+    pub fn set_heap_growth(&mut self, chunk_bytes: Option<usize>) {
+        self.heap_growth_chunk = chunk_bytes.filter(|&c| c > 0);
+    }
+
+    /// Returns (vectors_indexed, capacity) for the HNSW index.
+    ///
+    /// The index is rebuilt at double capacity automatically when an insert
+    /// would overflow it, so every node stays searchable.
+    pub fn index_capacity(&self) -> (usize, usize) {
+        (self.index.len(), self.index.capacity())
+    }
+
+    /// Number of distinct edges. Each `add_edge` stores the link in both
+    /// endpoints' adjacency lists (a self-loop once), and repeating it stores
+    /// it again, so every pair is counted once from its lower endpoint.
+    pub fn edge_count_total(&self) -> usize {
+        self.edge_list.iter()
+            .enumerate()
+            .map(|(id, neighbors)| neighbors.iter().filter(|&&n| n >= id as u64).collect::<HashSet<_>>().len())
+            .sum()
+    }
+
+    /// Length every embedding in the database must have: fixed by the first
+    /// node added, None while the database is empty. `add_node` and searches
+    /// reject other lengths with ValueError.
+    pub fn dimension(&self) -> Option<usize> {
+        self.embeddings.dim()
+    }
+
+    /// Size counters in one call: live/deleted nodes, distinct edges, heap and
+    /// dead heap bytes, and indexed vectors vs. index capacity.
+    pub fn stats(&self) -> HashMap<&'static str, usize> {
+        let live = self.headers.iter().filter(|h| h.is_live()).count();
+        let (indexed, capacity) = self.index_capacity();
+        HashMap::from([
+            ("nodes", live),
+            ("deleted_nodes", self.headers.len() - live),
+            ("edges", self.edge_count_total()),
+            ("heap_bytes", self.heap_size()),
+            ("dead_bytes", self.dead_bytes()),
+            ("indexed_vectors", indexed),
+            ("index_capacity", capacity),
+        ])
+    }
+
+    /// Checks the arena invariants and returns every violation found, one
+    /// message each (an empty list means the database is consistent).
+    #[pyo3(name = "validate")]
+    pub fn py_validate(&self) -> Vec<String> {
+        self.validate().err().unwrap_or_default()
+    }
+
+    /// Returns the size of the content heap in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.data_heap.len()
+    }
+
+    /// Bytes in the content heap not referenced by any live node.
+    pub fn dead_bytes(&self) -> usize {
+        let live: usize = self.headers.iter()
+            .filter(|h| h.is_live())
+            .map(|h| h.data_len as usize)
+            .sum();
+        self.data_heap.len().saturating_sub(live)
+    }
+
+    /// Full compaction: drops deleted nodes for good and renumbers the
+    /// survivors densely (in their current order), rewriting the heap, edges,
+    /// clusters and the HNSW index to match.
+    ///
+    /// Returns the id remapping as {old_id: new_id}; deleted ids are absent.
+    /// Anything holding node ids outside the database must be updated with it.
+    pub fn compact(&mut self) -> HashMap<u64, u64> {
+        let live: Vec<u64> = self.headers.iter().filter(|h| h.is_live()).map(|h| h.id).collect();
+        let id_map: HashMap<u64, u64> = live.iter()
+            .enumerate()
+            .map(|(new_id, &old_id)| (old_id, new_id as u64))
+            .collect();
+
+        let mut headers = Vec::with_capacity(live.len());
+        let mut access = Vec::with_capacity(live.len());
+        let mut data_heap = Vec::with_capacity(live.iter().map(|&id| self.headers[id as usize].data_len as usize).sum());
+        let mut edge_list = Vec::with_capacity(live.len());
+        let mut edge_created = Vec::with_capacity(live.len());
+        let mut embeddings = Vec::with_capacity(live.len());
+        let mut old_embeddings = std::mem::take(self.embeddings.to_mut());
+
+        for &old_id in &live {
+            let mut header = self.header_view(old_id);
+            let start = header.data_offset as usize;
+            let end = (start + header.data_len as usize).min(self.data_heap.len());
+            header.id = id_map[&old_id];
+            header.data_offset = data_heap.len() as u64;
+            data_heap.extend_from_slice(self.data_heap.get(start..end).unwrap_or_default());
+
+            // Deleting a node unlinks it, so every neighbor is live
+            let neighbors: Vec<u64> = self.edge_list[old_id as usize].iter().map(|n| id_map[n]).collect();
+            header.edge_count = neighbors.len() as u32;
+            edge_list.push(neighbors);
+            edge_created.push(std::mem::take(&mut self.edge_created[old_id as usize]));
+            embeddings.push(std::mem::take(&mut old_embeddings[old_id as usize]));
+            access.push(bio::AccessCounters::from_header(&header));
+            headers.push(header);
+        }
+
+        self.headers = headers;
+        self.access = access;
+        self.data_heap = storage::Heap::Owned(data_heap);
+        self.edge_list = edge_list;
+        self.edge_created = edge_created;
+        self.index.rebuild(&embeddings);
+        self.embeddings = embeddings.into();
+        self.compact_cursor = 0;
+        self.compact_closed = 0;
+        self.content_cache.get_mut().unwrap_or_else(|e| e.into_inner()).clear(); // Ids were renumbered
+
+        self.content_index.clear();
+        for header in &self.headers {
+            if let Some(bytes) = storage::read_content(header, &self.data_heap) {
+                self.content_index.entry(storage::content_hash(&bytes)).or_default().push(header.id);
+            }
+        }
+        if let Some(clusters) = &mut self.clusters {
+            crate::cluster::remap_members(clusters, &id_map);
+        }
+
+        id_map
+    }
+
+    /// Incremental heap compaction: slides live content down over dead space,
+    /// moving roughly `max_bytes` of content per call (at least one node).
+    ///
+    /// Progress is kept between calls, so repeated calls finish a full pass even
+    /// while nodes are added or deleted. Node ids never change; only content
+    /// offsets do. Returns the dead bytes squeezed out by this call (0 when it
+    /// only shifted content). The heap shrinks when a pass ends, so callers can
+    /// simply repeat while `dead_bytes()` is non-zero.
+    pub fn compact_step(&mut self, max_bytes: usize) -> usize {
+        let mut segments: Vec<(u64, u64)> = self.headers.iter()
+            .filter(|h| h.is_live() && h.data_offset as usize >= self.compact_cursor)
+            .map(|h| (h.data_offset, h.id))
+            .collect();
+        segments.sort_unstable();
+
+        let mut moved = 0;
+        let mut closed = 0;
+        for (offset, id) in segments {
+            if moved > 0 && moved >= max_bytes {
+                // Budget spent; resume from the cursor next call
+                self.compact_closed += closed;
+                return closed;
+            }
+
+            let offset = offset as usize;
+            let len = self.headers[id as usize].data_len as usize;
+            let gap = offset - self.compact_cursor;
+            if gap > 0 {
+                self.data_heap.to_mut().copy_within(offset..offset + len, self.compact_cursor);
+                self.headers[id as usize].data_offset = self.compact_cursor as u64;
+                moved += len;
+            }
+            // The gap also holds the dead bytes already squeezed out this pass
+            closed += gap - (self.compact_closed + closed);
+            self.compact_cursor += len;
+        }
+
+        // Pass complete: everything past the cursor is dead
+        let trailing = self.data_heap.len() - self.compact_cursor - (self.compact_closed + closed);
+        self.data_heap.to_mut().truncate(self.compact_cursor);
+        self.compact_cursor = 0;
+        self.compact_closed = 0;
+
+        closed + trailing
+    }
+
+    /// Sets the average-linkage similarity below which `build_clusters` stops
+    /// merging (default 0.20). Raising it yields more, tighter clusters, possibly
+    /// more than the requested `k_clusters`. Takes effect on the next build.
+    pub fn set_cluster_similarity_threshold(&mut self, threshold: f32) {
+        self.cluster_config.similarity_threshold = threshold;
     }
 
     /// Builds or rebuilds the cluster hierarchy
-    pub fn build_clusters(&mut self, k_clusters: Option<usize>) -> PyResult<()> {
+    ///
+    /// Aims for `k_clusters` root clusters (default 10), but merging stops once
+    /// no pair of clusters is more similar than the similarity threshold (see
+    /// `set_cluster_similarity_threshold`), so there can be more roots than that.
+    ///
+    /// On a rebuild, anchors from the previous hierarchy are kept wherever they
+    /// still score close to the best member, so cluster labels stay stable.
+    ///
+    /// `progress`, if given, is called as `progress(merges_done, merges_total)`
+    /// after every merge. Pending signals are checked at the same points, so
+    /// Ctrl-C (or an exception raised by `progress`) aborts the run and leaves
+    /// the previous clusters in place.
+    pub fn build_clusters(
+        &mut self,
+        py: Python<'_>,
+        k_clusters: Option<usize>,
+        progress: Option<PyObject>,
+    ) -> PyResult<()> {
         let k = k_clusters.unwrap_or(10); // Default: 10 root clusters
         
         let engine = ClusterEngine::new(self.cluster_config.clone());
+        let mut interrupted = None;
+        let embeddings = self.embeddings.to_vecs();
         
-        self.clusters = Some(engine.cluster_graph(
+        let mut clusters = engine.cluster_graph_with_progress(
             &self.headers,
-            &self.embeddings,
+            &embeddings,
             &self.edge_list,
             k,
-        ));
+            |done, total| {
+                let result = py.check_signals().and_then(|_| match &progress {
+                    Some(callback) => callback.call1(py, (done, total)).map(|_| ()),
+                    None => Ok(()),
+                });
+                match result {
+                    Ok(()) => true,
+                    Err(e) => {
+                        interrupted = Some(e);
+                        false
+                    }
+                }
+            },
+        );
+
+        if let Some(e) = interrupted {
+            return Err(e);
+        }
+        // Keep anchors from the previous build where they're still good
+        if let (Some(new), Some(prior)) = (&mut clusters, &self.clusters) {
+            engine.keep_prior_anchors(new, prior, &embeddings, &self.headers);
+        }
+        drop(embeddings);
+        self.clusters = clusters;
+        self.inserted_since_cluster = 0;
         
         Ok(())
     }
 
+    /// Cold-start pipeline for a freshly loaded corpus: rebuilds the HNSW
+    /// index in one pass (with new `m` / `ef_construction` if given) and then
+    /// runs `build_clusters`. The hierarchy is kept on the database, so
+    /// `hybrid_search` and friends use it without any further wiring.
+    pub fn prepare(
+        &mut self,
+        py: Python<'_>,
+        k_clusters: Option<usize>,
+        m: Option<usize>,
+        ef_construction: Option<usize>,
+        progress: Option<PyObject>,
+    ) -> PyResult<()> {
+        self.index.reconfigure(self.embeddings.to_mut(), m, ef_construction);
+        self.build_clusters(py, k_clusters, progress)
+    }
+
+    /// Builds the cluster hierarchy with seeded k-means (k-means++ start)
+    /// instead of agglomerative merging. Much faster on large databases, and
+    /// the same `seed` (default 0) on the same data gives identical clusters.
+    pub fn build_clusters_kmeans(&mut self, k_clusters: Option<usize>, seed: Option<u64>) {
+        let engine = ClusterEngine::new(self.cluster_config.clone());
+        let k = k_clusters.unwrap_or(10);
+        let clusters = engine.cluster_graph_kmeans(&self.headers, &self.embeddings.to_vecs(), k, seed.unwrap_or(0));
+        self.clusters = Some(clusters);
+        self.inserted_since_cluster = 0;
+    }
+
+    /// Builds the cluster hierarchy with the number of root clusters in
+    /// `[k_min, k_max]` (default 2..8) that gives the best mean silhouette,
+    /// for when the right `k_clusters` isn't known. Returns the chosen count.
+    pub fn auto_cluster(&mut self, k_min: Option<usize>, k_max: Option<usize>) -> usize {
+        let engine = ClusterEngine::new(self.cluster_config.clone());
+        let k_range = (k_min.unwrap_or(2), k_max.unwrap_or(8));
+        let embeddings = self.embeddings.to_vecs();
+        let mut clusters = engine.auto_cluster(&self.headers, &embeddings, k_range);
+        if let Some(prior) = &self.clusters {
+            engine.keep_prior_anchors(&mut clusters, prior, &embeddings, &self.headers);
+        }
+        drop(embeddings);
+        let k = clusters.len();
+        self.clusters = Some(clusters);
+        self.inserted_since_cluster = 0;
+        k
+    }
+
+    /// Suggests a number of root clusters from the graph's shape: the usual
+    /// sqrt(n / 2) estimate shrunk by mean degree (a dense, well-connected
+    /// graph holds together in fewer clusters), but never fewer than the
+    /// number of connected components with at least one edge. Nodes without
+    /// edges don't add to the count. Returns 0 for an empty database.
+    pub fn suggest_k(&self) -> usize {
+        let live = self.headers.iter().filter(|h| h.is_live()).count();
+        if live == 0 {
+            return 0;
+        }
+        let components = graph::connected_components(&self.edge_list)
+            .iter()
+            .filter(|c| c.len() > 1)
+            .count();
+        let mean_degree = 2.0 * self.edge_count_total() as f64 / live as f64;
+        let estimate = ((live as f64 / 2.0).sqrt() / (1.0 + mean_degree).sqrt()).round() as usize;
+        estimate.max(components).clamp(1, live)
+    }
+
+    /// Rebuilds the hierarchy (same number of root clusters, via
+    /// `build_clusters`) if more than `drift_threshold` of the live nodes were
+    /// inserted since it was last built. Does nothing if there is no hierarchy
+    /// yet. Returns whether it reclustered.
+    ///
+    /// The insert count isn't saved with the database, so it restarts at 0
+    /// after `load` / `open_mmap`: inserts made before the last save don't
+    /// count towards the drift. Call `build_clusters` after loading if the
+    /// saved hierarchy may be stale.
+    pub fn maybe_recluster(&mut self, py: Python<'_>, drift_threshold: f32) -> PyResult<bool> {
+        let Some(clusters) = &self.clusters else {
+            return Ok(false);
+        };
+        let live = self.headers.iter().filter(|h| h.is_live()).count();
+        let drift = self.inserted_since_cluster as f32 / live.max(1) as f32;
+        if drift <= drift_threshold {
+            return Ok(false);
+        }
+        let k = clusters.len();
+        self.build_clusters(py, Some(k), None)?;
+        Ok(true)
+    }
+
+    /// Drops the stored hierarchy; searches then rank without a cluster score
+    /// until the next build. Persisted on the next `save`.
+    pub fn clear_clusters(&mut self) {
+        self.clusters = None;
+    }
+
     /// Get all clusters (returns simplified structure for Python)
     pub fn get_clusters(&self) -> Vec<(u64, u64, Vec<u64>, f32)> {
         match &self.clusters {
@@ -252,12 +994,48 @@ impl SpiderDB {
         })
     }
 
+    /// Detailed stats for one cluster (root or sub-cluster) by id.
+    /// Raises KeyError if no such cluster exists.
+    pub fn cluster_stats(&self, cluster_id: u64) -> PyResult<ClusterStats> {
+        let cluster = self.clusters.as_deref()
+            .and_then(|clusters| crate::cluster::find_cluster(clusters, cluster_id))
+            .ok_or(SpiderError::ClusterNotFound(cluster_id))?;
+
+        let engine = ClusterEngine::new(self.cluster_config.clone());
+        Ok(engine.cluster_stats(cluster, &self.embeddings))
+    }
+
+    /// The `k` (default 5) clusters nearest to `cluster_id` by centroid cosine,
+    /// among clusters at the same depth, as (cluster_id, similarity) most
+    /// similar first. Raises KeyError if no such cluster exists.
+    pub fn cluster_neighbors(&self, cluster_id: u64, k: Option<usize>) -> PyResult<Vec<(u64, f32)>> {
+        let clusters = self.clusters.as_deref()
+            .filter(|clusters| crate::cluster::find_cluster(clusters, cluster_id).is_some())
+            .ok_or(SpiderError::ClusterNotFound(cluster_id))?;
+
+        let engine = ClusterEngine::new(self.cluster_config.clone());
+        Ok(engine.cluster_neighbors(cluster_id, clusters, k.unwrap_or(5)))
+    }
+
+    /// Get cluster coverage: (covered_count, uncovered_node_ids)
+    /// Before build_clusters() every node is reported as uncovered.
+    /// Deleted nodes are never reported.
+    pub fn get_cluster_coverage(&self) -> (usize, Vec<u64>) {
+        let engine = ClusterEngine::new(self.cluster_config.clone());
+        let clusters = self.clusters.as_deref().unwrap_or(&[]);
+        let (covered, uncovered) = engine.coverage(clusters, self.headers.len());
+        let uncovered = uncovered.into_iter()
+            .filter(|&id| self.headers[id as usize].is_live())
+            .collect();
+        (covered, uncovered)
+    }
+
     /// Export cluster hierarchy as string (for debugging)
     pub fn export_cluster_tree(&self) -> String {
         match &self.clusters {
             Some(clusters) => {
                 clusters.iter()
-                    .map(|c| crate::cluster::export_cluster_tree(c))
+                    .map(crate::cluster::export_cluster_tree)
                     .collect::<Vec<_>>()
                     .join("\n")
             }
@@ -266,135 +1044,1037 @@ impl SpiderDB {
     }
 
     /// Adds a Bi-directional edge from source to target.
-    pub fn add_edge(&mut self, source_id: u64, target_id: u64) {
-        if source_id as usize >= self.headers.len() || target_id as usize >= self.headers.len() {
-            return;
+    /// Raises KeyError if either node doesn't exist. Self-loops follow
+    /// `set_self_loops` (dropped by default).
+    pub fn add_edge(&mut self, source_id: u64, target_id: u64) -> PyResult<()> {
+        self.header(source_id)?;
+        self.header(target_id)?;
+        if source_id == target_id {
+            match self.self_loops {
+                graph::SelfLoops::Allow => {}
+                graph::SelfLoops::Drop => return Ok(()),
+                graph::SelfLoops::Reject => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!("Self-loop on node {} rejected", source_id)));
+                }
+            }
         }
         
+        let now = self.now();
+
         // 1. Add Forward Link (Source -> Target) [Child]
         self.edge_list[source_id as usize].push(target_id);
+        self.edge_created[source_id as usize].push(now);
         self.headers[source_id as usize].edge_count += 1;
 
         // 2. Add Backward Link (Target -> Source) [Parent]
+        if source_id == target_id {
+            return Ok(()); // An allowed self-loop is stored once
+        }
         self.edge_list[target_id as usize].push(source_id);
+        self.edge_created[target_id as usize].push(now);
         self.headers[target_id as usize].edge_count += 1;
+
+        Ok(())
     }
 
-    /// Retrieves a node's content by ID.
-    pub fn get_node(&mut self, id: u64) -> Option<String> {
-        if id as usize >= self.headers.len() {
-            return None;
+    /// Removes every listed edge in one pass, from both endpoints' adjacency.
+    ///
+    /// Direction doesn't matter since links are stored both ways; duplicate
+    /// links between the same pair all go. Pairs that aren't linked (or name
+    /// unknown nodes) are ignored. Returns the number of distinct edges removed.
+    pub fn remove_edges_bulk(&mut self, edges: Vec<(u64, u64)>) -> usize {
+        let mut targets: HashMap<u64, HashSet<u64>> = HashMap::new();
+        let mut pairs = HashSet::new();
+        for (a, b) in edges {
+            let linked = self.edge_list.get(a as usize).is_some_and(|list| list.contains(&b));
+            if linked && pairs.insert((a.min(b), a.max(b))) {
+                targets.entry(a).or_default().insert(b);
+                targets.entry(b).or_default().insert(a);
+            }
         }
 
-        let header = &mut self.headers[id as usize];
-        
-        // Update Bio-Metrics
-        header.last_access_ts = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        header.access_count += 1;
-
-        let start = header.data_offset as usize;
-        let end = start + header.data_len as usize;
-        
-        if end > self.data_heap.len() {
-            return None;
+        for (&node, drop) in &targets {
+            self.retain_edges(node, |x| !drop.contains(&x));
+            self.headers[node as usize].edge_count = self.edge_list[node as usize].len() as u32;
         }
-
-        let bytes = &self.data_heap[start..end];
-        String::from_utf8(bytes.to_vec()).ok()
+        pairs.len()
     }
 
-    /// --- Get Neighbors ---
-    pub fn get_neighbors(&self, id: u64) -> Vec<u64> {
-        if id as usize >= self.edge_list.len() {
-            return Vec::new();
+    /// Folds `duplicates` into the canonical node `keep` and deletes them.
+    ///
+    /// Every edge touching a duplicate is re-pointed at `keep` (deduplicated, no
+    /// self-loops) and their access counts are added to `keep`'s. Raises KeyError
+    /// if any id is unknown or already deleted. Returns the number of nodes removed.
+    pub fn merge_nodes(&mut self, keep: u64, duplicates: Vec<u64>) -> PyResult<usize> {
+        self.header(keep)?;
+        let mut removed = HashSet::new();
+        for &dup in &duplicates {
+            self.header(dup)?;
+            if dup != keep {
+                removed.insert(dup);
+            }
         }
-        // Return a clone of the neighbor list
-        self.edge_list[id as usize].clone()
+
+        for &dup in &removed {
+            for &n in &self.edge_list[dup as usize].clone() {
+                if n != keep && !removed.contains(&n) && !self.edge_list[keep as usize].contains(&n) {
+                    self.add_edge(keep, n)?;
+                }
+            }
+            let count = self.header_view(dup).access_count;
+            self.access[keep as usize].add_count(count);
+        }
+
+        self.delete_nodes(&removed);
+        Ok(removed.len())
     }
 
-    /// Performs a hybrid search combining vector similarity, biological score, and cluster awareness.
-    /// It identifies and re-ranks candidates from clusters or HNSW based on a combined score.
+    /// Memory consolidation: replaces groups of similar low-significance nodes
+    /// with one summary node each.
     ///
-    /// # Arguments
-    /// * `query_embedding` - A vector of floats representing the query embedding.
-    /// * `k` - The desired number of top results to return.
-    /// * `ef_search` - An optional parameter for the HNSW search, controlling the size of the
-    ///                 dynamic list of neighbors during search. If `None`, a default is used.
+    /// Live, unpinned nodes with significance <= `max_significance` qualify. Each
+    /// group starts at the lowest remaining qualifying id and takes every other
+    /// qualifying node whose cosine similarity to it is >= `similarity_threshold`;
+    /// groups of one are left alone. `summarize_fn(contents)` turns the members'
+    /// contents into the summary text (default: joined with newlines). The
+    /// summary gets the members' mean embedding and highest significance, and
+    /// the members are then merged into it as with `merge_nodes`.
     ///
-    /// # Returns
-    /// A `Vec` of tuples, where each tuple contains a node ID (`u64`) and its
-    /// combined similarity score (`f32`), sorted in descending order of score.
-    pub fn hybrid_search(
-        &mut self, 
-        query_embedding: Vec<f32>, 
-        k: usize, 
-        ef_search: Option<usize>
-    ) -> Vec<(u64, f32)> {
-        let config = ranking::RankConfig::default();
+    /// All summaries are produced before anything changes, so an exception from
+    /// `summarize_fn` leaves the database untouched. Returns the summary ids.
+    pub fn consolidate(
+        &mut self,
+        py: Python<'_>,
+        similarity_threshold: f32,
+        max_significance: u8,
+        summarize_fn: Option<PyObject>,
+    ) -> PyResult<Vec<u64>> {
+        let mut pending: Vec<u64> = self.headers.iter()
+            .filter(|h| h.is_live() && !h.is_pinned() && h.significance <= max_significance)
+            .map(|h| h.id)
+            .collect();
 
-        // 1. Get Candidates (Cluster-aware or Raw Index)
-        let candidates = if self.clusters.is_some() {
-            ranking::find_cluster_candidates(self.clusters.as_ref().unwrap(), &query_embedding, k * 3)
-        } else {
-            self.index.search(&query_embedding, k * 3, ef_search).into_iter().map(|(id, _)| id).collect()
-        };
+        let mut groups = Vec::new();
+        while !pending.is_empty() {
+            let leader = pending.remove(0);
+            let leader_emb = self.embeddings.vector(leader as usize);
+            let (members, rest): (Vec<u64>, Vec<u64>) = pending.into_iter().partition(|&id| {
+                search::cosine_similarity(&leader_emb, &self.embeddings.vector(id as usize)) >= similarity_threshold
+            });
+            pending = rest;
+            if !members.is_empty() {
+                groups.push(std::iter::once(leader).chain(members).collect::<Vec<u64>>());
+            }
+        }
 
-        // 2. Expand Graph
-        let pool = ranking::expand_with_neighbors(&candidates, &self.edge_list, 2);
-        let mut scored = Vec::new();
+        let mut summaries = Vec::with_capacity(groups.len());
+        for group in &groups {
+            let contents: Vec<String> = group.iter()
+                .map(|&id| {
+                    self.content(id)
+                        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                        .unwrap_or_default()
+                })
+                .collect();
+            let summary = match &summarize_fn {
+                Some(f) => f.call1(py, (contents,))?.extract::<String>(py)?,
+                None => contents.join("\n"),
+            };
+            summaries.push(summary);
+        }
 
-        // 3. Score Everything
-        for id in pool {
-            if id as usize >= self.embeddings.len() { continue; }
-            
-            let semantic = search::cosine_similarity(&query_embedding, &self.embeddings[id as usize]);
-            let graph = ranking::calculate_graph_score(id, &self.edge_list, &self.embeddings, &candidates, &query_embedding);
-            let bio = ranking::calculate_bio_score(&self.headers[id as usize]);
-            let cluster = ranking::calculate_cluster_score(id, self.clusters.as_ref(), &query_embedding);
+        let mut summary_ids = Vec::with_capacity(groups.len());
+        for (group, summary) in groups.into_iter().zip(summaries) {
+            let dim = self.embeddings.vector(group[0] as usize).len();
+            let mut centroid = vec![0.0; dim];
+            for &id in &group {
+                for (c, v) in centroid.iter_mut().zip(self.embeddings.vector(id as usize).iter()) {
+                    *c += v / group.len() as f32;
+                }
+            }
+            let significance = group.iter().map(|&id| self.headers[id as usize].significance).max().unwrap_or(0);
 
-            let total = (semantic * config.semantic_weight) + 
-                        (graph * config.graph_weight) + 
-                        (bio * config.bio_weight) + 
-                        (cluster * config.cluster_weight);
+            let summary_id = self.insert_node(summary.as_bytes(), centroid, significance);
+            self.merge_nodes(summary_id, group)?;
+            summary_ids.push(summary_id);
+        }
 
-            if total >= 0.25 {
-                scored.push((id, total));
+        Ok(summary_ids)
+    }
+
+    /// Retrieves a node's content by ID.
+    ///
+    /// Raises KeyError for an unknown id, RuntimeError if the stored content is
+    /// truncated, and ValueError if it isn't valid UTF-8.
+    pub fn get_node(&self, id: u64) -> PyResult<String> {
+        self.header(id)?;
+        let bytes = self.content(id).ok_or(SpiderError::Truncated(id))?;
+        let text = String::from_utf8(bytes.into_owned()).map_err(|_| SpiderError::InvalidUtf8(id))?;
+
+        // Update Bio-Metrics
+        self.access[id as usize].record(self.now(), &self.bio_config);
+
+        Ok(text)
+    }
+
+    /// Content, significance, access metrics and life score of a node in one
+    /// call, or None if there's no such live node. Unlike `get_node` this does
+    /// not count as an access. The embedding is included only if asked for.
+    pub fn fetch(&self, id: u64, include_embedding: Option<bool>) -> PyResult<Option<NodeRecord>> {
+        if self.header(id).is_err() {
+            return Ok(None);
+        }
+        let header = self.header_view(id);
+        let bytes = self.content(id).ok_or(SpiderError::Truncated(id))?;
+        let content = String::from_utf8(bytes.into_owned()).map_err(|_| SpiderError::InvalidUtf8(id))?;
+
+        Ok(Some(NodeRecord {
+            id,
+            content,
+            embedding: include_embedding.unwrap_or(false).then(|| self.embeddings.vector(id as usize).into_owned()),
+            significance: header.significance,
+            access_count: header.access_count,
+            last_access_ts: header.last_access_ts,
+            recent_access: header.recent_access,
+            life_score: self.bio_config.life_score(&header, self.now()),
+        }))
+    }
+
+    /// The first `max_bytes` bytes of a node's content, cut back to a UTF-8
+    /// character boundary, for result lists.
+    ///
+    /// Uncompressed content is read straight from the heap without copying the
+    /// rest. Does not touch access metrics. None for unknown or deleted nodes
+    /// and content that isn't valid UTF-8.
+    pub fn content_preview(&self, id: u64, max_bytes: usize) -> Option<String> {
+        self.header(id).ok()?;
+        let content = match self.content_bytes(id) {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None => self.content(id)?,
+        };
+        let prefix = &content[..content.len().min(max_bytes)];
+        match std::str::from_utf8(prefix) {
+            Ok(text) => Some(text.to_string()),
+            // Only a character split by the cut is tolerated
+            Err(e) if e.error_len().is_none() => String::from_utf8(prefix[..e.valid_up_to()].to_vec()).ok(),
+            Err(_) => None,
+        }
+    }
+
+    /// Literal substring search over node content; returns matching live ids in
+    /// id order, at most `max_results` of them (default: all).
+    ///
+    /// A linear scan of the heap, for exact strings (error codes, names) that
+    /// semantic search can't pin down. UTF-8 is self-synchronizing, so a byte
+    /// match is always a whole-character match. `case_insensitive` compares
+    /// Unicode-lowercased text; `whole_word` skips matches inside a longer word
+    /// (see `search::text_contains`). Does not touch access metrics.
+    pub fn search_text(
+        &self,
+        substring: String,
+        max_results: Option<usize>,
+        case_insensitive: Option<bool>,
+        whole_word: Option<bool>,
+    ) -> Vec<u64> {
+        let case_insensitive = case_insensitive.unwrap_or(false);
+        let whole_word = whole_word.unwrap_or(false);
+        let needle = substring.as_bytes();
+        self.headers.iter()
+            .filter(|h| h.is_live())
+            .filter(|h| {
+                self.content(h.id).is_some_and(|bytes| {
+                    if case_insensitive || whole_word {
+                        let text = String::from_utf8_lossy(&bytes);
+                        search::text_contains(&text, &substring, case_insensitive, whole_word)
+                    } else {
+                        needle.is_empty() || bytes.windows(needle.len()).any(|window| window == needle)
+                    }
+                })
+            })
+            .map(|h| h.id)
+            .take(max_results.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    /// Every live node with no edges at all (links are stored on both ends, so
+    /// an empty adjacency list means zero in- and out-degree).
+    pub fn isolated_nodes(&self) -> Vec<u64> {
+        self.headers.iter()
+            .filter(|h| h.is_live() && self.edge_list[h.id as usize].is_empty())
+            .map(|h| h.id)
+            .collect()
+    }
+
+    /// --- Get Neighbors ---
+    pub fn get_neighbors(&self, id: u64) -> PyResult<Vec<u64>> {
+        self.header(id)?;
+        // Return a clone of the neighbor list
+        Ok(self.edge_list[id as usize].clone())
+    }
+
+    /// Extracts the induced subgraph within `hops` of the seed nodes.
+    ///
+    /// Returns the sorted node set and every edge whose endpoints are both in it,
+    /// each undirected edge listed once as (smaller_id, larger_id).
+    pub fn subgraph(&self, seeds: Vec<u64>, hops: usize) -> (Vec<u64>, Vec<(u64, u64)>) {
+        let seeds: Vec<u64> = seeds.into_iter()
+            .filter(|&id| self.header(id).is_ok())
+            .collect();
+
+        let mut nodes = ranking::expand_with_neighbors(&seeds, &self.edge_list, hops);
+        nodes.sort_unstable();
+        let members: HashSet<u64> = nodes.iter().copied().collect();
+
+        let mut edges = BTreeSet::new();
+        for &source in &nodes {
+            for &target in &self.edge_list[source as usize] {
+                if members.contains(&target) {
+                    edges.insert((source.min(target), source.max(target)));
+                }
             }
         }
 
-        // 4. Sort and Update Access
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        let final_results: Vec<(u64, f32)> = scored.into_iter().take(k).collect();
+        (nodes, edges.into_iter().collect())
+    }
+
+    /// Draws `n` distinct live nodes (all of them if there are fewer), each
+    /// with probability proportional to significance + 1, so significant
+    /// nodes are over-represented but nothing is excluded outright. The same
+    /// `seed` gives the same sample. Returned in draw order.
+    pub fn representative_sample(&self, n: usize, seed: u64) -> Vec<u64> {
+        // Weighted sampling without replacement (Efraimidis-Spirakis): give
+        // each node the key u^(1/w) and keep the n largest keys.
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut keyed: Vec<(f64, u64)> = self.headers.iter()
+            .filter(|h| h.is_live())
+            .map(|h| {
+                let weight = h.significance as f64 + 1.0;
+                (rng.gen::<f64>().powf(1.0 / weight), h.id)
+            })
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keyed.into_iter().take(n).map(|(_, id)| id).collect()
+    }
+
+    /// Nodes whose removal would disconnect part of the graph (edges taken as
+    /// undirected). Useful for spotting the few nodes holding clusters together.
+    pub fn articulation_points(&self) -> Vec<u64> {
+        graph::articulation_points(&self.edge_list)
+    }
+
+    /// Edges whose removal would disconnect part of the graph, as (lower id,
+    /// higher id) pairs.
+    pub fn bridges(&self) -> Vec<(u64, u64)> {
+        graph::bridges(&self.edge_list)
+    }
+
+    /// Random walk of up to `steps` hops from `start`. Each hop picks a
+    /// neighbor with probability proportional to its edge weight (see
+    /// `get_edge_weight`; negative weights count as zero, and a node whose
+    /// edges all weigh zero picks uniformly).
+    ///
+    /// `restart_prob` (default 0.0) is the per-step chance of jumping back to
+    /// `start`. A fixed `seed` makes the walk reproducible. Returns the visited
+    /// sequence including `start`; empty if `start` doesn't exist.
+    pub fn random_walk(
+        &self,
+        start: u64,
+        steps: usize,
+        seed: u64,
+        restart_prob: Option<f32>,
+    ) -> Vec<u64> {
+        if self.header(start).is_err() {
+            return Vec::new();
+        }
+        graph::random_walk(&self.edge_list, start, steps, seed, restart_prob.unwrap_or(0.0), |a, b| {
+            graph::edge_weight(&self.edge_list, &self.embeddings, a, b).unwrap_or(0.0)
+        })
+    }
+
+    /// Nodes exactly `hop` edges from `start` (by shortest path), excluding
+    /// everything closer. Empty if `start` doesn't exist or the graph runs out.
+    pub fn neighbors_at_hop(&self, start: u64, hop: usize) -> Vec<u64> {
+        if self.header(start).is_err() {
+            return Vec::new();
+        }
+        graph::neighbors_at_hop(&self.edge_list, start, hop)
+    }
+
+    /// Lowest-cost path between two nodes, weighting each edge by the cosine
+    /// similarity of its endpoints.
+    ///
+    /// By default a strong edge is cheap (cost = 1 - weight), so the path follows
+    /// the closest relationships; set `weight_as_cost` to use the weight itself as
+    /// the cost. Returns (path, total_cost), or None if `to` is unreachable.
+    ///
+    /// With `weight_as_cost`, an edge between anti-correlated nodes has a
+    /// negative cost, which Dijkstra can't handle: rather than quietly
+    /// treating it as free, the search raises ValueError when it reaches one.
+    pub fn shortest_path_weighted(
+        &self,
+        from: u64,
+        to: u64,
+        weight_as_cost: Option<bool>,
+    ) -> PyResult<Option<(Vec<u64>, f32)>> {
+        let weight_as_cost = weight_as_cost.unwrap_or(false);
+        let now = self.now();
+        let path = graph::shortest_path_weighted(&self.edge_list, from, to, |a, b| {
+            let weight = graph::edge_weight(&self.edge_list, &self.embeddings, a, b).unwrap_or(0.0)
+                * self.edge_decay(a, b, now);
+            // Rounding can push a cosine just past 1.0
+            if weight_as_cost { weight } else { (1.0 - weight).max(0.0) }
+        })?;
+        Ok(path)
+    }
+
+    /// Strength of the edge `source -> target` (cosine similarity of its
+    /// endpoints), or None if there is no such edge.
+    pub fn get_edge_weight(&self, source: u64, target: u64) -> Option<f32> {
+        graph::edge_weight(&self.edge_list, &self.embeddings, source, target)
+    }
+
+    /// Performs a hybrid search combining vector similarity, biological score, and cluster awareness.
+    /// It identifies and re-ranks candidates from clusters or HNSW based on a combined score.
+    ///
+    /// # Arguments
+    /// * `query_embedding` - A vector of floats representing the query embedding.
+    /// * `k` - The desired number of top results to return.
+    /// * `ef_search` - An optional parameter for the HNSW search, controlling the size of the
+    ///                 dynamic list of neighbors during search. If `None`, a default is used.
+    /// * `normalize` - If true, min-max normalize scores over this query's candidate pool
+    ///                 into [0, 1], so a fixed threshold means the same across queries.
+    /// * `softmax_temperature` - If set, turn the returned scores into a softmax at this
+    ///                 temperature: nonnegative, summing to 1.0, order unchanged.
+    ///                 Applied after `normalize`.
+    /// * `dedup_by_content` - If true, keep only the best-ranked node per content hash,
+    ///                 over-fetching candidates so up to `k` distinct results remain.
+    /// * `with_components` - If true, also report the two main factors of each score
+    ///                 (for logging and offline A/B tests of the blend), see below.
+    ///
+    /// # Returns
+    /// A `Vec` of tuples, where each tuple contains a node ID (`u64`) and its
+    /// combined similarity score (`f32`), sorted in descending order of score.
+    ///
+    /// With `with_components` each tuple is (id, similarity, life_score, combined).
+    /// `similarity` is the semantic term under the current metric (after the
+    /// similarity floor) and `life_score` the node's life score when it was
+    /// ranked (after the `min_life_score` floor). `combined` is the score
+    /// returned without the flag: under the default "sum" combiner the
+    /// weighted sum of the semantic, graph, bio and cluster terms (the bio term
+    /// is derived from the life score), under "product" (see
+    /// `set_score_combiner`) exactly `similarity * life_score`. The output
+    /// options above apply to it as usual.
+    // Mirrors the Python keyword arguments; `SearchQuery` is the structured form
+    #[allow(clippy::too_many_arguments)]
+    pub fn hybrid_search(
+        &self, 
+        query_embedding: Vec<f32>, 
+        k: usize, 
+        ef_search: Option<usize>,
+        normalize: Option<bool>,
+        softmax_temperature: Option<f32>,
+        dedup_by_content: Option<bool>,
+        with_components: Option<bool>,
+    ) -> PyResult<SearchResults> {
+        let mut query = SearchQuery::new(query_embedding, k);
+        query.ef_search = ef_search;
+        query.normalize = normalize.unwrap_or(false);
+        query.softmax_temperature = softmax_temperature;
+        query.dedup_by_content = dedup_by_content.unwrap_or(false);
+        query.with_components = with_components.unwrap_or(false);
+        self.run(&query)
+    }
+
+    /// Runs a `SearchQuery`: `hybrid_search` with the query's exclusions,
+    /// significance band, weight overrides and output options all applied.
+    /// Gives the same results as the equivalent `hybrid_search*` call.
+    ///
+    /// A query with zero norm or the wrong dimension raises ValueError, or
+    /// returns no results, per `set_invalid_query_mode`.
+    pub fn run(&self, query: &SearchQuery) -> PyResult<SearchResults> {
+        let results = self.rank(query)?;
+
+        let output = if query.with_components {
+            let now = self.now();
+            let config = &self.rank_config;
+            SearchResults::Components(results.iter()
+                .map(|&(id, combined)| {
+                    let similarity = config.metric.similarity(&query.embedding, &self.embeddings.vector(id as usize))
+                        .max(config.similarity_floor);
+                    (id, similarity, self.ranked_life_score(id, now), combined)
+                })
+                .collect())
+        } else {
+            SearchResults::Scores(results.clone())
+        };
 
         // Update bio-metrics for the winners
-        for (id, _) in &final_results {
+        for (id, _) in &results {
             self.update_node_access(*id);
         }
 
-        final_results
+        Ok(output)
+    }
+
+    /// `hybrid_search` with each result's deepest containing cluster attached,
+    /// as (node_id, score, cluster_id) for grouping results in a UI.
+    ///
+    /// `cluster_id` is None when clusters haven't been built or the node was
+    /// added after the last build.
+    pub fn hybrid_search_with_clusters(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        ef_search: Option<usize>,
+    ) -> PyResult<Vec<(u64, f32, Option<u64>)>> {
+        let results = self.hybrid_search(query_embedding, k, ef_search, None, None, None, None)?.into_scores();
+        Ok(results.into_iter()
+            .map(|(id, score)| (id, score, self.get_node_clusters(id).last().copied()))
+            .collect())
+    }
+
+    /// Like `hybrid_search`, but embeds `text` by calling `embed_fn(text)`.
+    ///
+    /// `embed_fn` must return a sequence of floats with the database's
+    /// embedding dimension; anything else raises ValueError / TypeError.
+    pub fn hybrid_search_text(
+        &self,
+        py: Python<'_>,
+        text: String,
+        k: usize,
+        embed_fn: PyObject,
+        ef_search: Option<usize>,
+    ) -> PyResult<Vec<(u64, f32)>> {
+        let query_embedding: Vec<f32> = embed_fn.call1(py, (text,))?.extract(py)?;
+        self.check_dimension(&query_embedding)?;
+
+        self.hybrid_search(query_embedding, k, ef_search, None, None, None, None).map(SearchResults::into_scores)
+    }
+
+    /// High-recall vector search: over-fetches `k * oversample` (default 4)
+    /// candidates from HNSW, recomputes their exact cosine similarity and
+    /// returns the best `k` in exact order.
+    ///
+    /// Fixes HNSW's approximate distances and ordering for the candidate set at
+    /// the cost of one cosine per candidate. Does not touch access metrics.
+    pub fn hybrid_search_verified(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        oversample: Option<usize>,
+        ef_search: Option<usize>,
+    ) -> Vec<(u64, f32)> {
+        let fetch = k.saturating_mul(oversample.unwrap_or(4).max(1));
+        let now = self.now();
+
+        let verified = self.nearest(&query_embedding, fetch, ef_search)
+            .into_iter()
+            .filter(|&(id, _)| {
+                self.header(id).is_ok_and(|h| self.is_retained(h, now))
+            })
+            .map(|(id, _)| (id, search::cosine_similarity(&query_embedding, &self.embeddings.vector(id as usize))));
+
+        search::top_k(verified, k)
+    }
+
+    /// Semantic + lexical search: every live node scores
+    /// `(1 - lexical_weight) * cosine + lexical_weight * lexical`, where the
+    /// lexical part is `search::lexical_score` of its content against the terms
+    /// of `query_text`.
+    ///
+    /// A node with the exact keywords can outrank a slightly closer paraphrase
+    /// when `lexical_weight` (default 0.3, clamped to [0, 1]) is high. Exact
+    /// over all nodes (O(n)); does not touch access metrics.
+    pub fn search_hybrid_lexical(
+        &self,
+        query_text: String,
+        query_embedding: Vec<f32>,
+        k: usize,
+        lexical_weight: Option<f32>,
+    ) -> PyResult<Vec<(u64, f32)>> {
+        self.check_dimension(&query_embedding)?;
+        let weight = lexical_weight.unwrap_or(0.3).clamp(0.0, 1.0);
+        let terms: Vec<String> = search::tokenize(&query_text)
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let now = self.now();
+
+        let scored = self.headers.iter()
+            .filter(|h| h.is_live() && self.is_retained(h, now))
+            .map(|h| {
+                let semantic = search::cosine_similarity(&query_embedding, &self.embeddings.vector(h.id as usize));
+                let lexical = self.content(h.id)
+                    .map_or(0.0, |bytes| search::lexical_score(&String::from_utf8_lossy(&bytes), &terms));
+                (h.id, (1.0 - weight) * semantic + weight * lexical)
+            });
+
+        Ok(search::top_k(scored, k))
+    }
+
+    /// Brute-force vector search: exact cosine against every live node.
+    ///
+    /// O(n) per query; the ground truth `hybrid_search_verified` approximates.
+    /// Scores stream through a k-sized heap (`search::top_k`) in one pass over
+    /// the embeddings, so extra memory is O(k) whatever the node count.
+    ///
+    /// The scan runs in chunks of `EXACT_SEARCH_CHUNK` nodes with the GIL
+    /// released, so other Python threads keep running; pending signals are
+    /// checked between chunks, so Ctrl-C interrupts a long search.
+    pub fn exact_search(&self, py: Python<'_>, query_embedding: Vec<f32>, k: usize) -> PyResult<Vec<(u64, f32)>> {
+        self.exact_search_chunked(py, &query_embedding, k, EXACT_SEARCH_CHUNK, |py| py.check_signals())
+    }
+
+    /// Names the embedding slice `[start, end)`, e.g. one head of vectors that
+    /// concatenate several, for `search_subspace`. Re-registering a name
+    /// replaces it. Raises ValueError for an empty range or one past the
+    /// stored dimension. Not persisted by `save`.
+    pub fn register_subspace(&mut self, name: String, start: usize, end: usize) -> PyResult<()> {
+        if start >= end {
+            return Err(SpiderError::InvalidConfig(format!("subspace '{}' range [{}, {}) is empty", name, start, end)).into());
+        }
+        if let Some(dim) = self.dimension() {
+            if end > dim {
+                return Err(SpiderError::InvalidConfig(format!(
+                    "subspace '{}' range [{}, {}) exceeds the embedding dimension {}", name, start, end, dim
+                )).into());
+            }
+        }
+        self.subspaces.insert(name, start..end);
+        Ok(())
+    }
+
+    /// Exact top-`k` by cosine over one named slice of the stored embeddings
+    /// (see `register_subspace`); the other dimensions are ignored.
+    /// `query_sub` has the slice's length. Raises KeyError for an unknown
+    /// name. Does not touch access metrics.
+    pub fn search_subspace(&self, name: &str, query_sub: Vec<f32>, k: usize) -> PyResult<Vec<(u64, f32)>> {
+        let range = self.subspaces.get(name).cloned().ok_or_else(|| {
+            pyo3::exceptions::PyKeyError::new_err(format!("Subspace '{}' not registered", name))
+        })?;
+        if query_sub.len() != range.len() {
+            return Err(SpiderError::DimensionMismatch { expected: range.len(), got: query_sub.len() }.into());
+        }
+
+        let now = self.now();
+        let scored = self.headers.iter()
+            .filter(|h| h.is_live() && self.is_retained(h, now))
+            .filter_map(|h| {
+                let embedding = self.embeddings.vector(h.id as usize);
+                let slice = embedding.get(range.clone())?;
+                Some((h.id, search::cosine_similarity(&query_sub, slice)))
+            });
+        Ok(search::top_k(scored, k))
+    }
+
+    /// Two-stage search using the cluster hierarchy as a coarse filter: ranks
+    /// root clusters by centroid similarity, then scores only the members of
+    /// the best `max_clusters` (default 3) by exact cosine.
+    ///
+    /// On well-clustered data this scans a small fraction of the nodes. Nodes
+    /// added since the last `build_clusters` belong to no cluster and are not
+    /// searched; without clusters this falls back to `exact_search`. Does not
+    /// touch access metrics.
+    pub fn cluster_guided_search(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        max_clusters: Option<usize>,
+    ) -> Vec<(u64, f32)> {
+        let Some(clusters) = &self.clusters else {
+            return self.exact_scan(&query_embedding, k, 0..self.headers.len(), self.now());
+        };
+
+        let mut ranked: Vec<(&Cluster, f32)> = clusters.iter()
+            .map(|c| (c, search::cosine_similarity(&query_embedding, &c.centroid)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let now = self.now();
+        let scored = ranked.into_iter()
+            .take(max_clusters.unwrap_or(3))
+            .flat_map(|(c, _)| c.member_ids.iter().copied())
+            .filter(|&id| self.header(id).is_ok_and(|h| self.is_retained(h, now)))
+            .map(|id| (id, search::cosine_similarity(&query_embedding, &self.embeddings.vector(id as usize))));
+
+        search::top_k(scored, k)
+    }
+
+    /// Hybrid search restricted to nodes whose significance lies in
+    /// `[min_significance, max_significance]` (inclusive).
+    ///
+    /// Out-of-band nodes are dropped before ranking, so they never appear even
+    /// when they are closer to the query than every in-band node.
+    pub fn hybrid_search_in_band(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        min_significance: u8,
+        max_significance: u8,
+        ef_search: Option<usize>,
+    ) -> PyResult<Vec<(u64, f32)>> {
+        let mut query = SearchQuery::new(query_embedding, k);
+        query.ef_search = ef_search;
+        query.significance_band = Some((min_significance, max_significance));
+        self.run(&query).map(SearchResults::into_scores)
+    }
+
+    /// Hybrid search that never returns any id in `exclude`, for "show me more"
+    /// paging over results already shown.
+    ///
+    /// Excluded nodes are dropped before the top-k cut and the candidate pool
+    /// is over-fetched, so up to `k` fresh results still come back.
+    pub fn hybrid_search_excluding(
+        &self,
+        query_embedding: Vec<f32>,
+        k: usize,
+        exclude: Vec<u64>,
+        ef_search: Option<usize>,
+    ) -> PyResult<Vec<(u64, f32)>> {
+        let mut query = SearchQuery::new(query_embedding, k);
+        query.ef_search = ef_search;
+        query.exclude = exclude.into_iter().collect();
+        self.run(&query).map(SearchResults::into_scores)
+    }
+
+    /// Ages edges: with `half_life_hours` set, an edge's contribution to graph
+    /// scoring and `shortest_path_weighted` halves every that many hours since
+    /// it was created, so recent links weigh more. None (default) disables it.
+    pub fn set_edge_decay(&mut self, half_life_hours: Option<f32>) {
+        self.rank_config.edge_half_life_hours = half_life_hours;
+    }
+
+    /// Sets the lower bound applied to query similarity before ranking blends it.
+    ///
+    /// The default of 0.0 treats anti-correlated nodes as merely unrelated, so a
+    /// negative cosine can never flip a node's combined score below neutral ones.
+    pub fn set_similarity_floor(&mut self, floor: f32) {
+        self.rank_config.similarity_floor = floor;
+    }
+
+    /// Sets (or with None clears) a node's TTL, counted from its creation time.
+    pub fn set_ttl(&mut self, id: u64, ttl_secs: Option<u32>) -> PyResult<()> {
+        self.header(id)?;
+        self.headers[id as usize].ttl_secs = ttl_secs.unwrap_or(0);
+        Ok(())
+    }
+
+    /// Pins a node: `vacuum`, `expire` and the retention policy skip it
+    /// unconditionally, whatever its life score or TTL.
+    pub fn pin(&mut self, id: u64) -> PyResult<()> {
+        self.header(id)?;
+        self.headers[id as usize].flags |= storage::FLAG_PINNED;
+        Ok(())
+    }
+
+    /// Removes a node's pin, making it subject to decay again.
+    pub fn unpin(&mut self, id: u64) -> PyResult<()> {
+        self.header(id)?;
+        self.headers[id as usize].flags &= !storage::FLAG_PINNED;
+        Ok(())
+    }
+
+    /// Deletes every node whose TTL has run out and returns their ids.
+    ///
+    /// Independent of life score: an expired node goes even if it is heavily
+    /// used. Expired nodes are already hidden from search before this runs.
+    pub fn expire(&mut self) -> Vec<u64> {
+        let now = self.now();
+        let expired: Vec<u64> = self.headers.iter()
+            .filter(|h| h.is_live() && h.is_expired(now))
+            .map(|h| h.id)
+            .collect();
+
+        self.delete_nodes(&expired.iter().copied().collect());
+        expired
+    }
+
+    /// Enables (default) or disables adaptive over-fetching in filtered
+    /// searches such as `hybrid_search_in_band`.
+    ///
+    /// When enabled and the filter leaves fewer than `k` results, the HNSW
+    /// fetch (and `ef_search`) doubles until `k` qualify, ending with an exact
+    /// scan if the index runs out. Trades latency for a reliable result count.
+    pub fn set_adaptive_overfetch(&mut self, enabled: bool) {
+        self.candidate_config.adaptive_overfetch = enabled;
+    }
+
+    /// Makes significant nodes easier to retrieve in hybrid searches.
+    ///
+    /// The `k` nodes closest to the query among those with significance at
+    /// least `min_significance` are added to the candidate pool by exact
+    /// comparison, so HNSW approximation can't drop them. They are still
+    /// ranked normally. Costs one cosine per significant node; None disables.
+    pub fn set_significance_boost(&mut self, min_significance: Option<u8>) {
+        self.candidate_config.boost_min_significance = min_significance;
+    }
+
+    /// How `add_edge(a, a)` is handled: "drop" (default) silently ignores it,
+    /// "reject" raises ValueError, "allow" stores it. Ranking, PageRank and
+    /// random walks skip self-loops whatever the setting.
+    pub fn set_self_loops(&mut self, mode: &str) -> PyResult<()> {
+        self.self_loops = graph::SelfLoops::parse(mode).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown self-loop mode '{}' (expected allow, drop or reject)", mode))
+        })?;
+        Ok(())
+    }
+
+    /// What hybrid search (and `run`) does with a query whose norm is zero or
+    /// not finite, or whose dimension differs from the stored embeddings:
+    /// "raise" (default) raises ValueError, "empty" returns no results.
+    pub fn set_invalid_query_mode(&mut self, mode: &str) -> PyResult<()> {
+        self.invalid_queries = search::InvalidQueryMode::parse(mode).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown invalid-query mode '{}' (expected raise or empty)", mode))
+        })?;
+        Ok(())
+    }
+
+    /// Selects the similarity metric used to score hybrid_search candidates:
+    /// "cosine" (default), "dot" or "l2".
+    ///
+    /// Dot and L2 are mapped into (0, 1] before the weighted blend so the
+    /// ranking weights keep their meaning; cosine is blended as is, in
+    /// [-1, 1], with negatives clipped by the similarity floor.
+    /// Candidate retrieval itself still uses the cosine HNSW index.
+    pub fn set_metric(&mut self, metric: &str) -> PyResult<()> {
+        self.rank_config.metric = search::Metric::parse(metric).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown metric '{}' (expected cosine, dot or l2)", metric))
+        })?;
+        Ok(())
+    }
+
+    /// Selects how a node's links to the query's seed candidates are rewarded
+    /// in graph scoring: "fraction" (default; share of all seeds), "floor"
+    /// (any seed link earns at least `floor`, in [0, 1]) or "log" (log-scaled,
+    /// so one link among many seeds still counts).
+    pub fn set_seed_bonus(&mut self, mode: &str, floor: Option<f32>) -> PyResult<()> {
+        self.rank_config.seed_bonus = ranking::SeedBonus::parse(mode, floor).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Unknown seed bonus '{}' (expected fraction, floor with a floor value, or log)", mode
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Selects how hybrid_search combines a candidate's terms: "sum" (default;
+    /// the weighted sum of semantic, graph, bio and cluster terms, dropping
+    /// totals below 0.25) or "product" (similarity times life score, with the
+    /// graph and cluster terms and the weights ignored).
+    pub fn set_score_combiner(&mut self, combiner: &str) -> PyResult<()> {
+        self.rank_config.combiner = ranking::ScoreCombiner::parse(combiner).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown score combiner '{}' (expected sum or product)", combiner))
+        })?;
+        Ok(())
+    }
+
+    /// Tunes how deep cluster-based candidate selection descends.
+    ///
+    /// Sub-clusters are visited only below a cluster whose query similarity
+    /// exceeds `descend_threshold` (default 0.5), and only those scoring above
+    /// `min_sub_similarity` (default 0.45); root clusters need more than
+    /// `min_root_similarity` (default 0.4). Lower values pull in more
+    /// sub-clusters. Arguments left as None keep their current value.
+    pub fn set_candidate_config(
+        &mut self,
+        descend_threshold: Option<f32>,
+        min_sub_similarity: Option<f32>,
+        min_root_similarity: Option<f32>,
+    ) {
+        let config = &mut self.candidate_config;
+        config.descend_threshold = descend_threshold.unwrap_or(config.descend_threshold);
+        config.min_sub_similarity = min_sub_similarity.unwrap_or(config.min_sub_similarity);
+        config.min_root_similarity = min_root_similarity.unwrap_or(config.min_root_similarity);
     }
 
     /// Identifies nodes that should be removed based on their Life Score.
+    /// Nodes expired by the retention policy (if any) or their TTL are flagged as well.
+    /// Pinned nodes are never flagged.
     pub fn vacuum(&self, threshold: f32) -> Vec<u64> {
+        let now = self.now();
         let mut dead_nodes = Vec::new();
-        for header in &self.headers {
-            let score = bio::calc_life_score(header);
-            if score < threshold {
+        for header in self.header_views().iter().filter(|h| h.is_live() && !h.is_pinned()) {
+            let score = self.bio_config.life_score(header, now);
+            if score < threshold || !self.is_retained(header, now) {
                 dead_nodes.push(header.id);
             }
         }
         dead_nodes
     }
 
-    /// Calculates the life score of a node.
-    pub fn calculate_life_score(&self, id: u64) -> f32 {
-        if id as usize >= self.headers.len() {
-            return 0.0;
+    /// `vacuum` followed by a full `compact`: deletes the flagged nodes, then
+    /// reclaims their heap bytes, embeddings and edges. Returns the removed
+    /// (pre-compaction) ids.
+    ///
+    /// Node ids shift: survivors are renumbered densely in their old order, as
+    /// with `compact`, so any id held outside the database is stale afterwards.
+    pub fn vacuum_compact(&mut self, threshold: f32) -> Vec<u64> {
+        let dead = self.vacuum(threshold);
+        self.delete_nodes(&dead.iter().copied().collect());
+        self.compact();
+        dead
+    }
+
+    /// Selects which nodes `trim_to_capacity` evicts first: "life_score"
+    /// (default, lowest life score), "lru" (least recently accessed) or "lfu"
+    /// (fewest accesses).
+    pub fn set_eviction_policy(&mut self, policy: &str) -> PyResult<()> {
+        self.eviction_policy = bio::EvictionPolicy::parse(policy).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Unknown eviction policy '{}' (expected life_score, lru or lfu)", policy))
+        })?;
+        Ok(())
+    }
+
+    /// Deletes nodes, in eviction-policy order, until at most `max_nodes` live
+    /// nodes remain, and returns the evicted ids. Pinned nodes are never
+    /// evicted, so more than `max_nodes` can remain if too many are pinned.
+    pub fn trim_to_capacity(&mut self, max_nodes: usize) -> Vec<u64> {
+        let now = self.now();
+        let live = self.headers.iter().filter(|h| h.is_live()).count();
+        if live <= max_nodes {
+            return Vec::new();
         }
-        bio::calc_life_score(&self.headers[id as usize])
+
+        let mut candidates: Vec<((f64, u64), u64)> = self.header_views().iter()
+            .filter(|h| h.is_live() && !h.is_pinned())
+            .map(|h| (self.eviction_policy.key(h, now, &self.bio_config), h.id))
+            .collect();
+        candidates.sort_by(|a, b| a.0.0.total_cmp(&b.0.0).then(a.0.1.cmp(&b.0.1)).then(a.1.cmp(&b.1)));
+
+        let evicted: Vec<u64> = candidates.into_iter().take(live - max_nodes).map(|(_, id)| id).collect();
+        self.delete_nodes(&evicted.iter().copied().collect());
+        evicted
+    }
+
+    /// Tunes the life score formula used by vacuum, ranking and `bio_pagerank`:
+    /// (access_count * frequency_weight + significance * significance_weight)
+    /// / (hours_idle + time_offset_hours) ^ decay_exponent.
+    ///
+    /// `min_life_score` floors the life score seen by search ranking (not by
+    /// vacuum), so nodes with no accesses yet aren't ranked as dead.
+    ///
+    /// `recent_access_half_life_hours` sets how fast the recent-access counter
+    /// behind the ranking's frequency term forgets old reads.
+    ///
+    /// Arguments left as None keep their current value (defaults 2.0, 10.0,
+    /// 2.0, 1.8, 0.0, 24.0). Raises ValueError, leaving the config unchanged,
+    /// for negative weights or floor, a non-positive offset, exponent or
+    /// half-life, or non-finite values.
+    pub fn set_bio_config(
+        &mut self,
+        frequency_weight: Option<f32>,
+        significance_weight: Option<f32>,
+        time_offset_hours: Option<f32>,
+        decay_exponent: Option<f32>,
+        min_life_score: Option<f32>,
+        recent_access_half_life_hours: Option<f32>,
+    ) -> PyResult<()> {
+        let current = self.bio_config;
+        self.bio_config = bio::BioConfig::new(
+            frequency_weight.unwrap_or(current.frequency_weight),
+            significance_weight.unwrap_or(current.significance_weight),
+            time_offset_hours.unwrap_or(current.time_offset_hours),
+            decay_exponent.unwrap_or(current.decay_exponent),
+            min_life_score.unwrap_or(current.min_life_score),
+            recent_access_half_life_hours.unwrap_or(current.recent_access_half_life_hours),
+        )?;
+        Ok(())
+    }
+
+    /// Sets the retention policy shared by `vacuum` and all searches.
+    ///
+    /// Nodes idle for more than `max_age_hours` become invisible to search and
+    /// eligible for vacuum, unless their significance is at least
+    /// `min_significance` (default 255). Pass `max_age_hours=None` to disable.
+    pub fn set_retention_policy(&mut self, max_age_hours: Option<f32>, min_significance: Option<u8>) {
+        self.retention = max_age_hours.map(|max_age_hours| bio::RetentionPolicy {
+            max_age_hours,
+            min_significance: min_significance.unwrap_or(u8::MAX),
+        });
+    }
+
+    /// Like `vacuum`, but never flags a node that anchors a cluster (at any depth)
+    /// in the current hierarchy, so the clusters keep pointing at live nodes.
+    pub fn vacuum_preserve_anchors(&self, threshold: f32) -> Vec<u64> {
+        let anchors = match &self.clusters {
+            Some(clusters) => crate::cluster::collect_anchor_ids(clusters),
+            None => return self.vacuum(threshold),
+        };
+
+        self.vacuum(threshold)
+            .into_iter()
+            .filter(|id| !anchors.contains(id))
+            .collect()
+    }
+
+    /// Calculates the life score of a node.
+    pub fn calculate_life_score(&self, id: u64) -> PyResult<f32> {
+        self.header(id)?;
+        Ok(self.bio_config.life_score(&self.header_view(id), self.now()))
+    }
+
+    /// Life score of a node projected `hours_ahead` hours from now, assuming
+    /// no further access, as (hours, score) pairs in the given order. Useful
+    /// for showing why a node is fading. Raises KeyError for unknown ids.
+    pub fn life_score_forecast(&self, id: u64, hours_ahead: Vec<u64>) -> PyResult<Vec<(u64, f32)>> {
+        self.header(id)?;
+        let header = self.header_view(id);
+        let now = self.now();
+        Ok(hours_ahead.into_iter()
+            .map(|hours| (hours, self.bio_config.life_score(&header, now.saturating_add(hours.saturating_mul(3600)))))
+            .collect())
+    }
+
+    /// (id, access_count, last_access_ts) for every live node, in id order,
+    /// for analysing hot and cold nodes outside the database.
+    pub fn access_report(&self) -> Vec<(u64, u32, u64)> {
+        self.headers.iter()
+            .filter(|h| h.is_live())
+            .map(|h| {
+                let view = self.header_view(h.id);
+                (view.id, view.access_count, view.last_access_ts)
+            })
+            .collect()
+    }
+
+    /// PageRank where the teleport distribution is proportional to life scores,
+    /// blending graph structure and bio-metrics into one importance measure.
+    ///
+    /// Returns (node_id, rank) for every node; ranks sum to ~1.0.
+    pub fn bio_pagerank(&self, damping: Option<f32>, iterations: Option<usize>) -> Vec<(u64, f32)> {
+        let damping = damping.unwrap_or(0.85);
+        let iterations = iterations.unwrap_or(20);
+
+        ranking::bio_pagerank(&self.edge_list, &self.header_views(), damping, iterations, self.now(), &self.bio_config)
+            .into_iter()
+            .enumerate()
+            .filter(|&(id, _)| self.headers[id].is_live())
+            .map(|(id, rank)| (id as u64, rank))
+            .collect()
+    }
+
+    /// Switches bio-metrics to a logical clock starting at `start_secs` (default 0).
+    ///
+    /// While enabled, every timestamp and decay computation (add_node, get_node,
+    /// search, vacuum, life scores) reads this clock instead of the wall clock,
+    /// so aging only happens through `advance_clock` and runs are replayable.
+    pub fn enable_logical_clock(&mut self, start_secs: Option<u64>) {
+        self.logical_clock = Some(start_secs.unwrap_or(0));
+    }
+
+    /// Returns bio-metrics to wall-clock time.
+    pub fn disable_logical_clock(&mut self) {
+        self.logical_clock = None;
+    }
+
+    /// Moves the logical clock forward by `seconds` and returns the new time.
+    /// Raises RuntimeError if the logical clock isn't enabled.
+    pub fn advance_clock(&mut self, seconds: u64) -> PyResult<u64> {
+        let clock = self.logical_clock.as_mut().ok_or_else(|| {
+            pyo3::exceptions::PyRuntimeError::new_err("Logical clock not enabled. Call enable_logical_clock() first.")
+        })?;
+        *clock = clock.saturating_add(seconds);
+        Ok(*clock)
     }
 
     // ========================================================================
@@ -446,52 +2126,366 @@ impl SpiderDB {
         }
     }
 
-    /// Saves the database and optionally notifies the visualization server.
-    /// 
-    /// If server_url is set and auto_notify is true, this will automatically
-    /// POST to the server's /api/notify endpoint after saving.
-    pub fn save(&self, path: Option<String>) -> PyResult<()> {
-        let target_path = match path {
-            Some(p) => p,
-            None => match &self.file_path {
-                Some(p) => p.clone(),
+    /// What changed going from this database to `other` (typically two saved
+    /// snapshots of the same data): nodes added and removed, significance
+    /// changes and edge additions/removals. Nodes are matched by content, so
+    /// the comparison works even after `compact` renumbered ids. When several
+    /// nodes share content, the lowest id stands for all of them.
+    pub fn diff(&self, other: &SpiderDB) -> DbDiff {
+        let (old_nodes, old_edges) = self.content_graph();
+        let (new_nodes, new_edges) = other.content_graph();
+        let mut diff = DbDiff::default();
+
+        for (hash, &new_id) in &new_nodes {
+            match old_nodes.get(hash) {
+                None => diff.added.push(new_id),
+                Some(&old_id) => {
+                    let old_sig = self.headers[old_id as usize].significance;
+                    let new_sig = other.headers[new_id as usize].significance;
+                    if old_sig != new_sig {
+                        diff.significance_changed.push((new_id, old_sig, new_sig));
+                    }
+                }
+            }
+        }
+        diff.removed = old_nodes.iter()
+            .filter(|(hash, _)| !new_nodes.contains_key(hash))
+            .map(|(_, &id)| id)
+            .collect();
+
+        let to_ids = |edges: &HashSet<(u64, u64)>, except: &HashSet<(u64, u64)>, nodes: &HashMap<u64, u64>| {
+            let mut ids: Vec<(u64, u64)> = edges.difference(except)
+                .map(|(a, b)| {
+                    let (a, b) = (nodes[a], nodes[b]);
+                    (a.min(b), a.max(b))
+                })
+                .collect();
+            ids.sort_unstable();
+            ids
+        };
+        diff.edges_added = to_ids(&new_edges, &old_edges, &new_nodes);
+        diff.edges_removed = to_ids(&old_edges, &new_edges, &old_nodes);
+
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.significance_changed.sort_unstable();
+        diff
+    }
+
+    /// Opens a saved database. Unlike the constructor, which starts an empty
+    /// database when the file is missing, this raises IOError in that case.
+    #[staticmethod]
+    pub fn load(path: String) -> PyResult<SpiderDB> {
+        if !Path::new(&path).exists() {
+            let missing = std::io::Error::new(std::io::ErrorKind::NotFound, format!("Database file not found: {}", path));
+            return Err(SpiderError::Io(missing).into());
+        }
+        SpiderDB::new(Some(path), None, None, None, None)
+    }
+
+    /// Opens a database saved with `save`, memory-mapping its content heap
+    /// read-only instead of reading it into RAM. Headers and edges are loaded
+    /// as usual.
+    ///
+    /// Embeddings are loaded and indexed with HNSW too, unless
+    /// `mapped_embeddings` is true: then each vector is read from the mapping
+    /// when a search needs it and no index is built, so searches scan every
+    /// node (as `exact_search` does). That trades search latency for keeping
+    /// the vectors out of RAM, where the arena and the index would otherwise
+    /// each hold a copy. Clustering copies
+    /// the vectors for the duration of the call; adding or updating a node
+    /// copies them into memory for good and builds the index.
+    ///
+    /// Meant for large read-mostly databases: content is paged in as nodes are
+    /// read. The first write to the heap (adding a node, compaction) copies it
+    /// into memory. The file must not be changed by anything else while it is
+    /// open; `save` to the same path is fine, as it replaces the file instead
+    /// of rewriting it.
+    #[staticmethod]
+    pub fn open_mmap(path: String, mapped_embeddings: Option<bool>) -> PyResult<Self> {
+        let file = File::open(&path).map_err(SpiderError::Io)?;
+        // SAFETY: the mapping is read-only, and the file must not be modified
+        // while open (see above); `save` never truncates a mapped file.
+        let map = Arc::new(unsafe { memmap2::Mmap::map(&file) }.map_err(SpiderError::Io)?);
+        let (body, version) = snapshot_body_offset(&map[..map.len().min(8)])?;
+
+        if !mapped_embeddings.unwrap_or(false) {
+            let (heap, mut snapshot) = decode_mapped::<Vec<f32>>(&map[body..], version)?.detach_heap(&map);
+            let embeddings = storage::Embeddings::Owned(std::mem::take(&mut snapshot.embeddings));
+            let data_heap = storage::Heap::Mapped { map, start: heap.start, len: heap.len() };
+            return Ok(Self::from_snapshot(
+                snapshot, data_heap, embeddings, path, None, None, None, storage::DEFAULT_SIGNIFICANCE,
+            ));
+        }
+
+        let (heap, snapshot) = decode_mapped::<storage::SkippedVector>(&map[body..], version)?.detach_heap(&map);
+        // bincode writes the fields back to back: the embeddings follow the
+        // edges (and from version 1 their timestamps) as a u64 count, then
+        // each vector as a u64 length and its little-endian f32s
+        let size = |value: &Vec<Vec<u64>>| bincode::serialized_size(value).map_err(load_err);
+        let mut offset = heap.end as u64 + size(&snapshot.edge_list)?;
+        if version > 0 {
+            offset += size(&snapshot.edge_created)?;
+        }
+        let read_len = |at: u64| {
+            map.get(at as usize..at as usize + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| load_err("embeddings run past the end of the file"))
+        };
+        if read_len(offset)? != snapshot.embeddings.len() as u64 {
+            return Err(load_err("embeddings are not where the snapshot layout puts them"));
+        }
+        offset += 8;
+        let mut slots = Vec::with_capacity(snapshot.embeddings.len());
+        for &storage::SkippedVector(len) in &snapshot.embeddings {
+            if read_len(offset)? != len as u64 {
+                return Err(load_err("embeddings are not where the snapshot layout puts them"));
+            }
+            slots.push((offset as usize + 8, len));
+            offset += 8 + 4 * len as u64;
+        }
+
+        let embeddings = storage::Embeddings::Mapped { map: Arc::clone(&map), slots };
+        let data_heap = storage::Heap::Mapped { map, start: heap.start, len: heap.len() };
+        Ok(Self::from_snapshot(snapshot, data_heap, embeddings, path, None, None, None, storage::DEFAULT_SIGNIFICANCE))
+    }
+
+    /// Saves the database and optionally notifies the visualization server.
+    /// 
+    /// If server_url is set and auto_notify is true, this will automatically
+    /// POST to the server's /api/notify endpoint after saving.
+    pub fn save(&self, path: Option<String>) -> PyResult<()> {
+        let target_path = match path {
+            Some(p) => p,
+            None => match &self.file_path {
+                Some(p) => p.clone(),
                 None => return Err(pyo3::exceptions::PyValueError::new_err("No path specified for save() and no db_path provided at init.")),
             },
         };
 
         let snapshot = SpiderSnapshot {
-            headers: self.headers.clone(),
-            data_heap: self.data_heap.clone(),
+            headers: self.header_views(),
+            data_heap: self.data_heap.to_vec(),
             edge_list: self.edge_list.clone(),
-            embeddings: self.embeddings.clone(),
+            edge_created: self.edge_created.clone(),
+            embeddings: self.embeddings.to_vecs().into_owned(),
             clusters: self.clusters.clone(),
             cluster_config: self.cluster_config.clone(),
         };
 
-        let file = File::create(&target_path).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-        let writer = BufWriter::new(file);
+        // A mapped heap still reads from its file, which must not be truncated
+        // under it: write alongside and rename over (the map keeps the old inode)
+        let write_path = if self.data_heap.is_mapped() || self.embeddings.is_mapped() {
+            format!("{}.tmp", target_path)
+        } else {
+            target_path.clone()
+        };
+
+        let file = File::create(&write_path).map_err(SpiderError::Io)?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(SNAPSHOT_MAGIC).map_err(SpiderError::Io)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes()).map_err(SpiderError::Io)?;
         
-        bincode::serialize_into(writer, &snapshot)
+        bincode::serialize_into(&mut writer, &snapshot)
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        writer.flush().map_err(SpiderError::Io)?;
+        if write_path != target_path {
+            drop(writer);
+            std::fs::rename(&write_path, &target_path).map_err(SpiderError::Io)?;
+        }
 
         // ====================================================================
         // AUTO-NOTIFY: If server URL is set and auto_notify is enabled,
         // POST to the server's notify endpoint to trigger realtime updates
         // ====================================================================
-        if self.auto_notify {
-            if let Some(ref url) = self.server_url {
-                if let Err(e) = self.send_notify(url) {
-                    // Log but don't fail - saving succeeded, notification is optional
-                    eprintln!("[SpiderDB] Auto-notify failed (save succeeded): {}", e);
-                }
-            }
-        }
+        // Log but don't fail - saving succeeded, notification is optional
+        self.try_notify();
             
         Ok(())
     }
 
+    /// Streams every live node as one JSON object per line to `path`.
+    ///
+    /// Nothing is buffered beyond a single record, so this works for databases
+    /// far larger than a single in-memory JSON string. Returns the node count.
+    pub fn export_jsonl(&self, path: String) -> PyResult<usize> {
+        let file = File::create(&path).map_err(SpiderError::Io)?;
+        let mut writer = BufWriter::new(file);
+        let mut written = 0;
+
+        for header in self.header_views().iter().filter(|h| h.is_live()) {
+            let content = self.content(header.id)
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_default();
+            let record = JsonlNode {
+                id: header.id,
+                content,
+                embedding: self.embeddings.vector(header.id as usize).into_owned(),
+                significance: header.significance,
+                access_count: header.access_count,
+                last_access_ts: header.last_access_ts,
+                recent_access: header.recent_access,
+                created_ts: header.created_ts,
+                ttl_secs: header.ttl_secs,
+                neighbors: self.edge_list[header.id as usize].clone(),
+                edge_created: self.edge_created[header.id as usize].clone(),
+            };
+
+            serde_json::to_writer(&mut writer, &record)
+                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+            writer.write_all(b"\n").map_err(SpiderError::Io)?;
+            written += 1;
+        }
+
+        writer.flush().map_err(SpiderError::Io)?;
+        Ok(written)
+    }
+
+    /// Writes the k-nearest-neighbor graph of all live nodes to `path` as CSV
+    /// lines `src,dst,similarity`.
+    ///
+    /// Neighbors come from the HNSW index (queried in parallel across nodes);
+    /// the similarity written is the exact cosine, and pairs below
+    /// `min_similarity` are dropped, so a node gets at most `k` lines.
+    /// Returns the number of edges written.
+    pub fn export_knn_graph(
+        &self,
+        k: usize,
+        path: String,
+        min_similarity: Option<f32>,
+        ef_search: Option<usize>,
+    ) -> PyResult<usize> {
+        let min_similarity = min_similarity.unwrap_or(f32::MIN);
+        let live: Vec<u64> = self.headers.iter().filter(|h| h.is_live()).map(|h| h.id).collect();
+
+        let rows: Vec<Vec<(u64, u64, f32)>> = live.par_iter()
+            .map(|&src| {
+                let emb = &self.embeddings.vector(src as usize);
+                // One extra result since the node usually finds itself
+                self.nearest(emb, k.saturating_add(1), ef_search)
+                    .into_iter()
+                    .filter(|&(dst, _)| dst != src && self.header(dst).is_ok())
+                    .map(|(dst, _)| (src, dst, search::cosine_similarity(emb, &self.embeddings.vector(dst as usize))))
+                    .filter(|&(_, _, sim)| sim >= min_similarity)
+                    .take(k)
+                    .collect()
+            })
+            .collect();
+
+        let file = File::create(&path).map_err(SpiderError::Io)?;
+        let mut writer = BufWriter::new(file);
+        let mut written = 0;
+        writeln!(writer, "src,dst,similarity").map_err(SpiderError::Io)?;
+        for (src, dst, sim) in rows.into_iter().flatten() {
+            writeln!(writer, "{},{},{}", src, dst, sim).map_err(SpiderError::Io)?;
+            written += 1;
+        }
+
+        writer.flush().map_err(SpiderError::Io)?;
+        Ok(written)
+    }
+
+    /// Reads a file written by `export_jsonl` line by line and appends its nodes.
+    ///
+    /// Imported nodes get fresh ids; their edges are remapped accordingly and
+    /// bio-metrics are preserved. No auto-linking is done. Returns the number
+    /// of nodes imported.
+    pub fn import_jsonl(&mut self, path: String) -> PyResult<usize> {
+        let file = File::open(&path).map_err(SpiderError::Io)?;
+        let reader = BufReader::new(file);
+
+        let mut id_map = HashMap::new();
+        let mut pending_edges = Vec::new();
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line.map_err(SpiderError::Io)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: JsonlNode = serde_json::from_str(&line).map_err(|e| {
+                pyo3::exceptions::PyValueError::new_err(format!("Line {}: {}", line_no + 1, e))
+            })?;
+
+            self.check_dimension(&record.embedding)?;
+            let new_id = self.insert_node(record.content.as_bytes(), record.embedding, record.significance);
+            let header = &mut self.headers[new_id as usize];
+            header.access_count = record.access_count;
+            header.last_access_ts = record.last_access_ts;
+            header.recent_access = record.recent_access;
+            if record.created_ts > 0 {
+                header.created_ts = record.created_ts;
+            }
+            header.ttl_secs = record.ttl_secs;
+            self.access[new_id as usize] = bio::AccessCounters::from_header(header);
+
+            id_map.insert(record.id, new_id);
+            pending_edges.push((new_id, record.neighbors, record.edge_created));
+        }
+
+        // Neighbors may reference later lines, so edges are wired up last.
+        // Edges without a recorded creation time count as created now.
+        let now = self.now();
+        for (new_id, neighbors, created) in pending_edges {
+            let (remapped, created): (Vec<u64>, Vec<u64>) = neighbors.iter()
+                .enumerate()
+                .filter_map(|(i, n)| Some((*id_map.get(n)?, created.get(i).copied().unwrap_or(now))))
+                .unzip();
+            self.headers[new_id as usize].edge_count = remapped.len() as u32;
+            self.edge_list[new_id as usize] = remapped;
+            self.edge_created[new_id as usize] = created;
+        }
+
+        Ok(id_map.len())
+    }
+
+    /// Writes node metadata as an Arrow IPC file (Feather v2) to `path`.
+    ///
+    /// Columns: id, content, significance, access_count, last_access_ts,
+    /// life_score; one row per live node. Load it with `pyarrow.ipc.open_file`,
+    /// `polars.read_ipc` or DuckDB. Returns the row count.
+    /// Only available when built with the `arrow` feature.
+    #[cfg(feature = "arrow")]
+    pub fn export_arrow(&self, path: String) -> PyResult<usize> {
+        let arrow_err = |e: arrow::error::ArrowError| pyo3::exceptions::PyRuntimeError::new_err(e.to_string());
+
+        let batch = self.to_arrow().map_err(arrow_err)?;
+        let file = File::create(&path).map_err(SpiderError::Io)?;
+        let mut writer = arrow::ipc::writer::FileWriter::try_new(BufWriter::new(file), &batch.schema())
+            .map_err(arrow_err)?;
+        writer.write(&batch).map_err(arrow_err)?;
+        writer.finish().map_err(arrow_err)?;
+
+        Ok(batch.num_rows())
+    }
+
+    /// Writes the same columns as `export_arrow` to a Parquet file at `path`
+    /// (Snappy-compressed). Returns the row count.
+    /// Only available when built with the `parquet` feature.
+    #[cfg(feature = "parquet")]
+    pub fn export_parquet(&self, path: String) -> PyResult<usize> {
+        use parquet::arrow::ArrowWriter;
+        use parquet::basic::Compression;
+        use parquet::file::properties::WriterProperties;
+
+        let batch = self.to_arrow()
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+        let parquet_err = |e: parquet::errors::ParquetError| pyo3::exceptions::PyRuntimeError::new_err(e.to_string());
+
+        let file = File::create(&path).map_err(SpiderError::Io)?;
+        let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props)).map_err(parquet_err)?;
+        writer.write(&batch).map_err(parquet_err)?;
+        writer.close().map_err(parquet_err)?;
+
+        Ok(batch.num_rows())
+    }
+
     /// Exports the entire graph for visualization.
-    pub fn get_all_graph_data(&self) -> (Vec<(u64, String, u8, Option<u64>)>, Vec<(u64, u64)>) {
+    ///
+    /// Node labels are the first `preview_len` characters of the content
+    /// (default 30), with "…" appended when the content is longer.
+    pub fn get_all_graph_data(&self, preview_len: Option<usize>) -> GraphExport {
+        let preview_len = preview_len.unwrap_or(30);
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
 
@@ -511,16 +2505,16 @@ impl SpiderDB {
         }
 
         // 1. Collect Nodes
-        for header in &self.headers {
+        for header in self.headers.iter().filter(|h| h.is_live()) {
             // Fetch a short snippet of text for the label
-            let start = header.data_offset as usize;
-            let end = start + header.data_len as usize;
-            
-            let label = if end <= self.data_heap.len() {
-                // Get first 30 chars of content
-                let slice = &self.data_heap[start..end];
-                let full_text = String::from_utf8_lossy(slice);
-                full_text.chars().take(30).collect::<String>()
+            let label = if let Some(slice) = self.content(header.id) {
+                // Cut on a char boundary, never mid-codepoint
+                let full_text = String::from_utf8_lossy(&slice);
+                let mut label: String = full_text.chars().take(preview_len).collect();
+                if label.len() < full_text.len() {
+                    label.push('…');
+                }
+                label
             } else {
                 format!("Node {}", header.id)
             };
@@ -543,64 +2537,2499 @@ impl SpiderDB {
 }
 
 impl SpiderDB {
-    /// Update access metrics when a node is retrieved
-    fn update_node_access(&mut self, node_id: u64) {
-        if node_id as usize >= self.headers.len() {
-            return;
+    /// Assembles a database from a loaded snapshot, rebuilding the HNSW
+    /// index and content index. `data_heap` and `embeddings` replace the
+    /// snapshot's, so either may be owned or memory-mapped; mapped embeddings
+    /// are left out of the index.
+    #[allow(clippy::too_many_arguments)]
+    fn from_snapshot<E>(
+        snapshot: SpiderSnapshot<Vec<u8>, E>,
+        data_heap: storage::Heap,
+        embeddings: storage::Embeddings,
+        db_path: String,
+        max_capacity: Option<usize>,
+        m: Option<usize>,
+        ef_construction: Option<usize>,
+        default_significance: u8,
+    ) -> SpiderDB {
+        // REBUILD INDEX (The Fix)
+        // We create a fresh index, sized to hold at least every stored
+        // vector, and re-insert all of them.
+        let capacity = max_capacity.unwrap_or(1_000_000).max(embeddings.len());
+        let mut index = search::VectorIndex::new(m, Some(capacity), ef_construction);
+        if let storage::Embeddings::Owned(vectors) = &embeddings {
+            for (i, vec) in vectors.iter().enumerate() {
+                index.add(i as u64, vec);
+            }
         }
 
-        let header = &mut self.headers[node_id as usize];
-        header.access_count += 1;
-        header.last_access_ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let mut content_index: HashMap<u64, Vec<u64>> = HashMap::new();
+        for header in &snapshot.headers {
+            if let Some(bytes) = storage::read_content(header, &data_heap) {
+                content_index.entry(storage::content_hash(&bytes)).or_default().push(header.id);
+            }
+        }
+
+        let access = snapshot.headers.iter().map(bio::AccessCounters::from_header).collect();
+
+        SpiderDB {
+            headers: snapshot.headers,
+            access,
+            data_heap,
+            edge_list: snapshot.edge_list,
+            edge_created: snapshot.edge_created,
+            embeddings,
+            index, // Rebuilt index
+            file_path: Some(db_path),
+            content_index,
+            content_bloom: None,
+            compression_threshold: None,
+            content_cache: Mutex::new(storage::ContentCache::new(DEFAULT_CONTENT_CACHE_CAPACITY)),
+            heap_growth_chunk: None,
+            compact_cursor: 0,
+            compact_closed: 0,
+            inserted_since_cluster: 0,
+            retention: None,
+            eviction_policy: bio::EvictionPolicy::LifeScore,
+            subspaces: HashMap::new(),
+            default_significance,
+            logical_clock: None,
+            clusters: snapshot.clusters,
+            cluster_config: snapshot.cluster_config,
+            rank_config: ranking::RankConfig::default(),
+            self_loops: graph::SelfLoops::Drop,
+            invalid_queries: search::InvalidQueryMode::Raise,
+            bio_config: bio::BioConfig::default(),
+            candidate_config: ranking::CandidateConfig::default(),
+            server_url: None,           // No server by default
+            auto_notify: true,           // Auto-notify enabled by default
+        }
     }
 
-    // ========================================================================
-    // INTERNAL HELPERS: Notification to visualization server
-    // ========================================================================
+    /// Borrows a node's raw content bytes straight out of the data heap (no copy).
+    ///
+    /// Returns None for unknown ids, a header pointing past the end of the heap,
+    /// or compressed nodes (which can't be borrowed; use `content`).
+    /// Does not touch access metrics.
+    pub fn content_bytes(&self, id: u64) -> Option<&[u8]> {
+        let header = self.headers.get(id as usize)?;
+        if header.flags & storage::FLAG_COMPRESSED != 0 {
+            return None;
+        }
+        let start = header.data_offset as usize;
+        let end = start + header.data_len as usize;
+        self.data_heap.get(start..end)
+    }
 
-    /// Try to send a notification to the server.
-    /// 
-    /// This is a silent helper that only notifies if:
-    /// 1. server_url is set
-    /// 2. auto_notify is enabled
-    /// 
-    /// Failures are logged but don't cause errors.
-    fn try_notify(&self) {
-        if self.auto_notify {
-            if let Some(ref url) = self.server_url {
-                if let Err(e) = self.send_notify(url) {
-                    eprintln!("[SpiderDB] Auto-notify failed: {}", e);
+    /// Borrows a node's content as `&str`, or None if it isn't valid UTF-8.
+    pub fn content_str(&self, id: u64) -> Option<&str> {
+        std::str::from_utf8(self.content_bytes(id)?).ok()
+    }
+
+    /// Node metadata as a single Arrow RecordBatch, one row per live node.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> Result<arrow::record_batch::RecordBatch, arrow::error::ArrowError> {
+        use arrow::array::{ArrayRef, Float32Array, StringArray, UInt32Array, UInt64Array, UInt8Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use std::sync::Arc;
+
+        let now = self.now();
+        let live: Vec<NodeHeader> = self.header_views().into_iter().filter(|h| h.is_live()).collect();
+
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("content", DataType::Utf8, false),
+            Field::new("significance", DataType::UInt8, false),
+            Field::new("access_count", DataType::UInt32, false),
+            Field::new("last_access_ts", DataType::UInt64, false),
+            Field::new("life_score", DataType::Float32, false),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(live.iter().map(|h| h.id))),
+            Arc::new(StringArray::from_iter_values(live.iter().map(|h| {
+                self.content(h.id)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .unwrap_or_default()
+            }))),
+            Arc::new(UInt8Array::from_iter_values(live.iter().map(|h| h.significance))),
+            Arc::new(UInt32Array::from_iter_values(live.iter().map(|h| h.access_count))),
+            Arc::new(UInt64Array::from_iter_values(live.iter().map(|h| h.last_access_ts))),
+            Arc::new(Float32Array::from_iter_values(live.iter().map(|h| self.bio_config.life_score(h, now)))),
+        ];
+
+        arrow::record_batch::RecordBatch::try_new(Arc::new(schema), columns)
+    }
+
+    /// A live node's header, or NodeNotFound (also for deleted nodes).
+    pub fn header(&self, id: u64) -> Result<&NodeHeader, SpiderError> {
+        self.headers.get(id as usize)
+            .filter(|h| h.is_live())
+            .ok_or(SpiderError::NodeNotFound(id))
+    }
+
+    /// Integrity check over all arenas.
+    ///
+    /// Verifies that the per-node arenas have one entry per header, ids match
+    /// positions, live content lies inside the heap, edges point at live nodes
+    /// and are mirrored on the other end, `edge_count` matches the adjacency,
+    /// embeddings share one dimension, every node is indexed (unless the
+    /// embeddings are served from the file), and clusters only
+    /// reference live nodes.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        let n = self.headers.len();
+
+        for (name, len) in [
+            ("embeddings", self.embeddings.len()),
+            ("edge_list", self.edge_list.len()),
+            ("edge_created", self.edge_created.len()),
+            ("access", self.access.len()),
+        ] {
+            if len != n {
+                errors.push(format!("{} has {} entries but there are {} headers", name, len, n));
+            }
+        }
+        // Mapped embeddings are scanned, not indexed (see `open_mmap`)
+        if !self.embeddings.is_mapped() && self.index.len() != n {
+            errors.push(format!("index holds {} vectors but there are {} headers", self.index.len(), n));
+        }
+        if !errors.is_empty() {
+            // Per-node checks below index all arenas by id
+            return Err(errors);
+        }
+
+        let dim = self.embeddings.dim().unwrap_or(0);
+        for (i, header) in self.headers.iter().enumerate() {
+            let id = i as u64;
+            if header.id != id {
+                errors.push(format!("header at position {} has id {}", i, header.id));
+            }
+            let embedding_len = self.embeddings.vector(i).len();
+            if embedding_len != dim {
+                errors.push(format!("node {} embedding has dimension {} (expected {})", i, embedding_len, dim));
+            }
+
+            let edges = &self.edge_list[i];
+            if self.edge_created[i].len() != edges.len() {
+                errors.push(format!("node {} has {} edges but {} edge timestamps", i, edges.len(), self.edge_created[i].len()));
+            }
+            if header.edge_count as usize != edges.len() {
+                errors.push(format!("node {} edge_count is {} but it has {} edges", i, header.edge_count, edges.len()));
+            }
+
+            if !header.is_live() {
+                if !edges.is_empty() {
+                    errors.push(format!("deleted node {} still has {} edges", i, edges.len()));
                 }
+                continue;
+            }
+
+            let end = header.data_offset.saturating_add(header.data_len as u64);
+            if end > self.data_heap.len() as u64 {
+                errors.push(format!(
+                    "node {} content [{}, {}) runs past the heap ({} bytes)",
+                    i, header.data_offset, end, self.data_heap.len()
+                ));
+            }
+
+            for &target in edges {
+                match self.headers.get(target as usize) {
+                    None => errors.push(format!("node {} has an edge to unknown node {}", i, target)),
+                    Some(t) if !t.is_live() => errors.push(format!("node {} has an edge to deleted node {}", i, target)),
+                    Some(_) if !self.edge_list[target as usize].contains(&id) => {
+                        errors.push(format!("edge {} -> {} has no reverse link", i, target))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if let Some(clusters) = &self.clusters {
+            let mut stack: Vec<&Cluster> = clusters.iter().collect();
+            while let Some(cluster) = stack.pop() {
+                for &member in &cluster.member_ids {
+                    if self.header(member).is_err() {
+                        errors.push(format!("cluster {} contains missing or deleted node {}", cluster.id, member));
+                    }
+                }
+                stack.extend(&cluster.sub_clusters);
             }
         }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
     }
-    
-    /// Send an HTTP POST to the server's notify endpoint.
-    /// 
-    /// This uses the reqwest blocking client to make a synchronous HTTP call.
-    /// The endpoint triggers a WebSocket broadcast to all connected clients.
-    fn send_notify(&self, server_url: &str) -> Result<(), String> {
-        // Construct the notify endpoint URL
-        let notify_url = format!("{}/api/notify", server_url.trim_end_matches('/'));
-        
-        // Use reqwest blocking client (sync)
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-        
-        // Send POST request
-        let response = client.post(&notify_url)
-            .send()
-            .map_err(|e| format!("Failed to send notify request: {}", e))?;
-        
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(format!("Server returned error status: {}", response.status()))
+
+    /// A copy of the node's header with its live access counters applied.
+    /// Panics on an out-of-range id; callers check it first.
+    fn header_view(&self, id: u64) -> NodeHeader {
+        let mut header = self.headers[id as usize];
+        self.access[id as usize].apply_to(&mut header);
+        header
+    }
+
+    /// `header_view` for every node, in id order.
+    fn header_views(&self) -> Vec<NodeHeader> {
+        (0..self.headers.len() as u64).map(|id| self.header_view(id)).collect()
+    }
+
+    /// Live nodes as content hash -> lowest id with that content, and edges as
+    /// unordered content-hash pairs. Used by `diff` to compare databases whose
+    /// ids don't line up.
+    fn content_graph(&self) -> (HashMap<u64, u64>, HashSet<(u64, u64)>) {
+        let mut nodes = HashMap::new();
+        let mut hashes = HashMap::new();
+        for header in self.headers.iter().filter(|h| h.is_live()) {
+            if let Some(bytes) = self.content(header.id) {
+                let hash = storage::content_hash(&bytes);
+                nodes.entry(hash).or_insert(header.id);
+                hashes.insert(header.id, hash);
+            }
+        }
+
+        let mut edges = HashSet::new();
+        for (&id, &hash) in &hashes {
+            for target in &self.edge_list[id as usize] {
+                if let Some(&target_hash) = hashes.get(target) {
+                    edges.insert((hash.min(target_hash), hash.max(target_hash)));
+                }
+            }
+        }
+        (nodes, edges)
+    }
+
+    /// `index.search`, falling back to an exact scan when HNSW returns fewer
+    /// than `k` hits though the index holds more: its recall is approximate,
+    /// and on small graphs it can miss nodes outright.
+    ///
+    /// Once `update_node` has replaced vectors, a hit may have come from a
+    /// node's old embedding, so hits are re-scored against the current ones.
+    ///
+    /// Embeddings served from the file have no index, so they are scanned.
+    fn nearest(&self, query: &[f32], k: usize, ef_search: Option<usize>) -> Vec<(u64, f32)> {
+        if self.embeddings.is_mapped() {
+            let scored = (0..self.embeddings.len()).filter_map(|i| {
+                let embedding = self.embeddings.vector(i);
+                (embedding.len() == query.len()).then(|| (i as u64, search::cosine_similarity(query, &embedding)))
+            });
+            return search::top_k(scored, k);
+        }
+        let stale = self.index.stale();
+        // A hit on an old vector may push a better node out of the first k
+        let mut hits = self.index.search(query, k + stale, ef_search);
+        if stale > 0 {
+            for (id, sim) in hits.iter_mut() {
+                *sim = search::cosine_similarity(query, &self.embeddings.vector(*id as usize));
+            }
+            hits = search::top_k(hits, k);
+        }
+        if hits.len() >= k.min(self.index.len()) {
+            return hits;
+        }
+        search::find_similar_vectors(query, &self.embeddings.to_vecs(), k)
+            .into_iter()
+            .map(|(i, sim)| (i as u64, sim))
+            .collect()
+    }
+
+    /// `exact_search` scanning `chunk` nodes at a time with the GIL released,
+    /// calling `interrupted` (with the GIL held) before each chunk and giving
+    /// up on its first error.
+    fn exact_search_chunked(
+        &self,
+        py: Python<'_>,
+        query_embedding: &[f32],
+        k: usize,
+        chunk: usize,
+        mut interrupted: impl FnMut(Python<'_>) -> PyResult<()>,
+    ) -> PyResult<Vec<(u64, f32)>> {
+        let now = self.now();
+        let mut best = Vec::new();
+        for start in (0..self.headers.len()).step_by(chunk.max(1)) {
+            interrupted(py)?;
+            let end = (start + chunk.max(1)).min(self.headers.len());
+            best = py.allow_threads(|| {
+                let scanned = self.exact_scan(query_embedding, k, start..end, now);
+                search::top_k(best.into_iter().chain(scanned), k)
+            });
+        }
+        Ok(best)
+    }
+
+    /// Exact top-`k` by cosine among the live, retained nodes with ids in `ids`.
+    fn exact_scan(&self, query_embedding: &[f32], k: usize, ids: std::ops::Range<usize>, now: u64) -> Vec<(u64, f32)> {
+        let scored = self.headers[ids].iter()
+            .filter(|h| h.is_live() && self.is_retained(h, now))
+            .map(|h| (h.id, search::cosine_similarity(query_embedding, &self.embeddings.vector(h.id as usize))));
+        search::top_k(scored, k)
+    }
+
+    /// Rejects an empty embedding, or one whose length differs from the
+    /// stored ones (see `dimension`).
+    fn check_dimension(&self, embedding: &[f32]) -> Result<(), SpiderError> {
+        if embedding.is_empty() {
+            return Err(SpiderError::EmptyEmbedding);
+        }
+        match self.dimension() {
+            Some(expected) if expected != embedding.len() => Err(SpiderError::DimensionMismatch {
+                expected,
+                got: embedding.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The ranking behind `run`, without recording the winners' accesses.
+    fn rank(&self, query: &SearchQuery) -> PyResult<Vec<(u64, f32)>> {
+        if let Err(e) = self.check_query(&query.embedding) {
+            return match self.invalid_queries {
+                search::InvalidQueryMode::Raise => Err(e.into()),
+                search::InvalidQueryMode::Empty => Ok(Vec::new()),
+            };
+        }
+
+        let mut config = self.rank_config.clone();
+        config.semantic_weight = query.semantic_weight.unwrap_or(config.semantic_weight);
+        config.graph_weight = query.graph_weight.unwrap_or(config.graph_weight);
+        config.bio_weight = query.bio_weight.unwrap_or(config.bio_weight);
+        config.cluster_weight = query.cluster_weight.unwrap_or(config.cluster_weight);
+
+        let allowed = |h: &NodeHeader| {
+            !query.exclude.contains(&h.id)
+                && query.significance_band.is_none_or(|(lo, hi)| (lo..=hi).contains(&h.significance))
+        };
+        let overfetch = query.is_filtered() && self.candidate_config.adaptive_overfetch;
+        let (embedding, k, ef_search) = (&query.embedding, query.k, query.ef_search);
+
+        let mut scored = self.score_candidates(embedding, k, ef_search, &config, allowed, overfetch);
+        if query.dedup_by_content {
+            let mut fetch = k.max(1);
+            scored = self.dedup_by_content(scored);
+            while scored.len() < k && fetch < self.headers.len() {
+                fetch *= 2;
+                scored = self.dedup_by_content(self.score_candidates(embedding, fetch, ef_search, &config, allowed, overfetch));
+            }
+        }
+        let (min, max) = scored.iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), &(_, s)| (lo.min(s), hi.max(s)));
+
+        // 4. Select Top-K
+        let mut final_results = search::top_k(scored, k);
+        if query.normalize {
+            ranking::min_max_normalize(&mut final_results, min, max);
+        }
+        if let Some(temperature) = query.softmax_temperature {
+            ranking::softmax(&mut final_results, temperature);
         }
+
+        Ok(final_results)
+    }
+
+    /// Rejects a query that can't be ranked: empty, wrong dimension, or a
+    /// norm that is zero or not finite.
+    fn check_query(&self, embedding: &[f32]) -> Result<(), SpiderError> {
+        self.check_dimension(embedding)?;
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm == 0.0 || !norm.is_finite() {
+            return Err(SpiderError::ZeroQuery);
+        }
+        Ok(())
+    }
+
+    /// A node's logical content: borrowed when stored plain, inflated when
+    /// compressed (served from the content cache when possible).
+    pub fn content(&self, id: u64) -> Option<Cow<'_, [u8]>> {
+        let header = self.headers.get(id as usize)?;
+        if header.flags & storage::FLAG_COMPRESSED == 0 {
+            return storage::read_content(header, &self.data_heap);
+        }
+
+        let mut cache = self.content_cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(bytes) = cache.get(id) {
+            cache.hits += 1;
+            return Some(Cow::Owned(bytes.to_vec()));
+        }
+        cache.misses += 1;
+        let bytes: Arc<[u8]> = storage::read_content(header, &self.data_heap)?.into_owned().into();
+        cache.insert(id, bytes.clone());
+        Some(Cow::Owned(bytes.to_vec()))
+    }
+
+    /// Copies embeddings served from the file into memory and builds the HNSW
+    /// index `open_mmap` skipped; a no-op once they're in memory.
+    fn load_embeddings(&mut self) {
+        if self.embeddings.is_mapped() {
+            self.index.rebuild(self.embeddings.to_mut());
+        }
+    }
+
+    /// Appends a node to all arenas (heap, index, embeddings, edges, headers)
+    /// without any auto-linking. Returns the new node id.
+    fn insert_node(&mut self, content: &[u8], embedding: Vec<f32>, significance: u8) -> u64 {
+        let id = self.headers.len() as u64;
+        let (data_offset, data_len, flags) = self.append_content(content);
+        self.index_content(id, content);
+
+        // Add to HNSW Index, rebuilding it larger first if it's at capacity
+        self.load_embeddings();
+        if self.index.is_full() {
+            self.index.grow(self.embeddings.to_mut());
+        }
+        self.index.add(id, &embedding);
+
+        // Keep raw embeddings for now
+        self.embeddings.to_mut().push(embedding);
+
+        // Initialize empty edge list for this new node
+        self.edge_list.push(Vec::new());
+        self.edge_created.push(Vec::new());
+
+        self.headers.push(NodeHeader {
+            id,
+            data_offset,
+            data_len,
+            edge_start: 0,
+            edge_count: 0,
+            last_access_ts: self.now(),
+            access_count: 0,
+            recent_access: 0.0,
+            significance,
+            flags,
+            created_ts: self.now(),
+            ttl_secs: 0,
+        });
+        self.access.push(bio::AccessCounters::from_header(&self.headers[id as usize]));
+        self.inserted_since_cluster += 1;
+
+        id
+    }
+
+    /// Appends `content` to the heap, compressed when over the compression
+    /// threshold and that actually saves space. Returns the new
+    /// (data_offset, data_len, flags) for the node's header.
+    fn append_content(&mut self, content: &[u8]) -> (u64, u32, u8) {
+        let data_offset = self.data_heap.len() as u64;
+        let compressed = match self.compression_threshold {
+            Some(limit) if content.len() > limit => {
+                Some(storage::compress(content)).filter(|c| c.len() < content.len())
+            }
+            _ => None,
+        };
+        let (stored, flags) = match &compressed {
+            Some(c) => (c.as_slice(), storage::FLAG_COMPRESSED),
+            None => (content, 0),
+        };
+
+        self.reserve_heap(stored.len());
+        self.data_heap.to_mut().extend_from_slice(stored);
+        (data_offset, stored.len() as u32, flags)
+    }
+
+    /// Registers `id`'s content in the content-hash index (and Bloom filter).
+    fn index_content(&mut self, id: u64, content: &[u8]) {
+        let hash = storage::content_hash(content);
+        self.content_index.entry(hash).or_default().push(id);
+        if let Some(bloom) = &mut self.content_bloom {
+            bloom.insert(hash);
+        }
+    }
+
+    /// Removes `id` from the content-hash index entry for its current content.
+    fn unindex_content(&mut self, id: u64) {
+        if let Some(bytes) = self.content(id) {
+            let hash = storage::content_hash(&bytes);
+            if let Some(entries) = self.content_index.get_mut(&hash) {
+                entries.retain(|&x| x != id);
+                if entries.is_empty() {
+                    self.content_index.remove(&hash);
+                }
+            }
+        }
+    }
+
+    /// Tombstones nodes: flags them deleted, unlinks every edge touching them and
+    /// removes them from the content index and the cached clusters.
+    fn delete_nodes(&mut self, ids: &HashSet<u64>) {
+        let mut touched = HashSet::new();
+        for &id in ids {
+            self.edge_created[id as usize].clear();
+            for n in std::mem::take(&mut self.edge_list[id as usize]) {
+                self.retain_edges(n, |x| x != id);
+                touched.insert(n);
+            }
+
+            self.unindex_content(id);
+
+            let header = &mut self.headers[id as usize];
+            header.flags |= storage::FLAG_DELETED;
+            header.edge_count = 0;
+        }
+
+        for n in touched {
+            self.headers[n as usize].edge_count = self.edge_list[n as usize].len() as u32;
+        }
+        if let Some(clusters) = &mut self.clusters {
+            crate::cluster::remove_members(clusters, ids);
+        }
+    }
+
+    /// Keeps only the edges of `node` whose target passes `keep` (creation times
+    /// stay aligned). Doesn't touch the reverse links or `edge_count`.
+    fn retain_edges<F: Fn(u64) -> bool>(&mut self, node: u64, keep: F) {
+        let (kept, created): (Vec<u64>, Vec<u64>) = self.edge_list[node as usize].iter()
+            .zip(&self.edge_created[node as usize])
+            .filter(|&(&x, _)| keep(x))
+            .unzip();
+        self.edge_list[node as usize] = kept;
+        self.edge_created[node as usize] = created;
+    }
+
+    /// Current time for bio-metrics: the logical clock if enabled, else the wall clock.
+    fn now(&self) -> u64 {
+        self.logical_clock.unwrap_or_else(bio::now_secs)
+    }
+
+    /// Whether the node is still visible at `now`: it is pinned, or its TTL
+    /// (if any) hasn't run out and the retention policy (if any) keeps it.
+    fn is_retained(&self, header: &NodeHeader, now: u64) -> bool {
+        header.is_pinned()
+            || (!header.is_expired(now)
+                && self.retention.is_none_or(|policy| policy.retains(&self.header_view(header.id), now)))
+    }
+
+    /// Age-decay multiplier for the edge `source -> target` at `now` (1.0 when
+    /// edge decay is off; the first matching edge if there are duplicates).
+    fn edge_decay(&self, source: u64, target: u64, now: u64) -> f32 {
+        let half_life = self.rank_config.edge_half_life_hours;
+        if half_life.is_none() {
+            return 1.0;
+        }
+        self.edge_list[source as usize].iter()
+            .position(|&n| n == target)
+            .map_or(1.0, |i| graph::age_decay(self.edge_created[source as usize][i], now, half_life))
+    }
+
+    /// Makes room for `additional` heap bytes according to the growth strategy.
+    fn reserve_heap(&mut self, additional: usize) {
+        let Some(chunk) = self.heap_growth_chunk else {
+            return; // Vec's amortized doubling
+        };
+
+        let heap = self.data_heap.to_mut();
+        if heap.capacity() - heap.len() < additional {
+            // Grow by whole chunks only
+            heap.reserve_exact(additional.div_ceil(chunk) * chunk);
+        }
+    }
+
+    /// Finds a node whose content is byte-for-byte equal to `content`.
+    fn find_by_content(&self, content: &[u8]) -> Option<u64> {
+        let hash = storage::content_hash(content);
+        if let Some(bloom) = &self.content_bloom {
+            if !bloom.contains(hash) {
+                return None;
+            }
+        }
+
+        let candidates = self.content_index.get(&hash)?;
+
+        // Hash collisions are possible, so confirm against the heap
+        candidates.iter().copied().find(|&id| self.content(id).as_deref() == Some(content))
+    }
+
+    /// Scores the candidate pool for a query (steps 1-3 of hybrid_search).
+    ///
+    /// Returns every candidate accepted by `filter` that clears the minimum
+    /// combined score, unsorted. With `overfetch` (meant for filtered searches)
+    /// the HNSW fetch keeps doubling while fewer than `k` candidates qualify;
+    /// once it would cover the whole index, a final exact pass seeds the pool
+    /// with the closest nodes that pass `filter`. Does not touch access metrics.
+    fn score_candidates<F: Fn(&NodeHeader) -> bool>(
+        &self,
+        query_embedding: &[f32],
+        k: usize,
+        ef_search: Option<usize>,
+        config: &ranking::RankConfig,
+        filter: F,
+        overfetch: bool,
+    ) -> Vec<(u64, f32)> {
+        let mut fetch = k.saturating_mul(3);
+        let candidates = self.gather_candidates(query_embedding, k, fetch, ef_search, true);
+        let mut scored = self.score_pool(query_embedding, &candidates, config, &filter);
+
+        let mut exhausted = false;
+        while overfetch && scored.len() < k && !exhausted {
+            fetch = fetch.saturating_mul(2);
+            let candidates = if fetch < self.index.len() {
+                let ef = ef_search.unwrap_or(64).max(fetch);
+                self.gather_candidates(query_embedding, k, fetch, Some(ef), false)
+            } else {
+                // HNSW recall is approximate even at full width, so scan exactly
+                exhausted = true;
+                let qualifying = self.headers.iter()
+                    .filter(|h| h.is_live() && filter(h))
+                    .map(|h| (h.id, search::cosine_similarity(query_embedding, &self.embeddings.vector(h.id as usize))));
+                search::top_k(qualifying, k.saturating_mul(3)).into_iter().map(|(id, _)| id).collect()
+            };
+            scored = self.score_pool(query_embedding, &candidates, config, &filter);
+        }
+
+        scored
+    }
+
+    /// Step 1 of hybrid_search: up to `fetch` candidate ids from the cluster
+    /// hierarchy (if built and `use_clusters`) or the HNSW index, plus any
+    /// significance-boosted nodes.
+    fn gather_candidates(
+        &self,
+        query_embedding: &[f32],
+        k: usize,
+        fetch: usize,
+        ef_search: Option<usize>,
+        use_clusters: bool,
+    ) -> Vec<u64> {
+        // 1. Get Candidates (Cluster-aware or Raw Index)
+        let mut candidates = match &self.clusters {
+            Some(clusters) if use_clusters => {
+                ranking::find_cluster_candidates(clusters, query_embedding, fetch, &self.candidate_config)
+            }
+            _ => self.nearest(query_embedding, fetch, ef_search).into_iter().map(|(id, _)| id).collect(),
+        };
+
+        // 1b. Significance boost: exact top-k among significant nodes
+        if let Some(min_significance) = self.candidate_config.boost_min_significance {
+            let significant = self.headers.iter()
+                .filter(|h| h.is_live() && h.significance >= min_significance)
+                .map(|h| (h.id, search::cosine_similarity(query_embedding, &self.embeddings.vector(h.id as usize))));
+            for (id, _) in search::top_k(significant, k) {
+                if !candidates.contains(&id) {
+                    candidates.push(id);
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Steps 2-3 of hybrid_search: expands `candidates` through the graph and
+    /// scores every node in the pool that passes `filter`.
+    fn score_pool<F: Fn(&NodeHeader) -> bool>(
+        &self,
+        query_embedding: &[f32],
+        candidates: &[u64],
+        config: &ranking::RankConfig,
+        filter: &F,
+    ) -> Vec<(u64, f32)> {
+        let now = self.now();
+
+        // 2. Expand Graph
+        let pool = ranking::expand_with_neighbors(candidates, &self.edge_list, 2);
+        let mut scored = Vec::new();
+
+        // 3. Score Everything
+        for id in pool {
+            if id as usize >= self.embeddings.len() { continue; }
+            let header = &self.headers[id as usize];
+            if !header.is_live() || !filter(header) || !self.is_retained(header, now) { continue; }
+            
+            let semantic = config.metric.similarity(query_embedding, &self.embeddings.vector(id as usize))
+                .max(config.similarity_floor);
+            if config.combiner == ranking::ScoreCombiner::Product {
+                scored.push((id, semantic * self.ranked_life_score(id, now)));
+                continue;
+            }
+            let graph = ranking::calculate_graph_score(
+                id, &self.edge_list, &self.embeddings, candidates, query_embedding, config,
+                |a, b| self.edge_decay(a, b, now),
+            );
+            let bio = ranking::calculate_bio_score(&self.header_view(id), now, &self.bio_config);
+            let cluster = ranking::calculate_cluster_score(id, self.clusters.as_ref(), query_embedding, config.metric);
+
+            let total = (semantic * config.semantic_weight) + 
+                        (graph * config.graph_weight) + 
+                        (bio * config.bio_weight) + 
+                        (cluster * config.cluster_weight);
+
+            if total >= 0.25 {
+                scored.push((id, total));
+            }
+        }
+
+        scored
+    }
+
+    /// Life score of `id` at `now` as search ranking sees it: floored at
+    /// `min_life_score`.
+    fn ranked_life_score(&self, id: u64, now: u64) -> f32 {
+        self.bio_config.life_score(&self.header_view(id), now).max(self.bio_config.min_life_score)
+    }
+
+    /// Keeps only the highest-scoring entry per content hash.
+    fn dedup_by_content(&self, mut scored: Vec<(u64, f32)>) -> Vec<(u64, f32)> {
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut seen = HashSet::new();
+        scored.retain(|&(id, _)| {
+            self.content(id).is_none_or(|bytes| seen.insert(storage::content_hash(&bytes)))
+        });
+        scored
+    }
+
+    /// Update access metrics when a node is retrieved
+    fn update_node_access(&self, node_id: u64) {
+        if let Some(counters) = self.access.get(node_id as usize) {
+            counters.record(self.now(), &self.bio_config);
+        }
+    }
+
+    // ========================================================================
+    // INTERNAL HELPERS: Notification to visualization server
+    // ========================================================================
+
+    /// Try to send a notification to the server.
+    /// 
+    /// This is a silent helper that only notifies if:
+    /// 1. server_url is set
+    /// 2. auto_notify is enabled
+    /// 
+    /// Failures are logged but don't cause errors. Only `save` calls this,
+    /// so the log says the save itself went through.
+    fn try_notify(&self) {
+        if self.auto_notify {
+            if let Some(ref url) = self.server_url {
+                if let Err(e) = self.send_notify(url) {
+                    eprintln!("[SpiderDB] Auto-notify failed (save succeeded): {}", e);
+                }
+            }
+        }
+    }
+    
+    /// Send an HTTP POST to the server's notify endpoint.
+    /// 
+    /// This uses the reqwest blocking client to make a synchronous HTTP call.
+    /// The endpoint triggers a WebSocket broadcast to all connected clients.
+    fn send_notify(&self, server_url: &str) -> Result<(), String> {
+        // Construct the notify endpoint URL
+        let notify_url = format!("{}/api/notify", server_url.trim_end_matches('/'));
+        
+        // Use reqwest blocking client (sync)
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        
+        // Send POST request
+        let response = client.post(&notify_url)
+            .send()
+            .map_err(|e| format!("Failed to send notify request: {}", e))?;
+        
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Server returned error status: {}", response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Database saved by the headerless (version 0) format: four nodes, edges
+    /// 0-1 (twice) and 1-2, node 1 read once.
+    const BASELINE_V0: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/baseline_v0.db");
+
+    fn open_baseline() -> SpiderDB {
+        SpiderDB::new(Some(BASELINE_V0.to_string()), None, None, None, None).unwrap()
+    }
+
+    fn content_str(db: &SpiderDB, id: u64) -> String {
+        String::from_utf8(db.content(id).unwrap().into_owned()).unwrap()
+    }
+
+    /// Empty database that is never saved.
+    fn memory_db() -> SpiderDB {
+        SpiderDB::new(Some("/nonexistent/spider-test.db".to_string()), Some(64), None, None, None).unwrap()
+    }
+
+    /// Adds a node without auto-linking.
+    fn add(db: &mut SpiderDB, content: &str, embedding: Vec<f32>) -> u64 {
+        db.add_node(content.to_string(), embedding, None, Some(2.0), None, None).unwrap()
+    }
+
+    #[test]
+    fn loads_baseline_v0_snapshot() {
+        let mapped = |embeddings| SpiderDB::open_mmap(BASELINE_V0.to_string(), Some(embeddings)).unwrap();
+        for db in [open_baseline(), mapped(false), mapped(true)] {
+            let contents: Vec<String> = (0..4).map(|id| content_str(&db, id)).collect();
+            assert_eq!(contents, ["alpha", "beta", "gamma", "delta"]);
+            let significance: Vec<u8> = db.headers.iter().map(|h| h.significance).collect();
+            assert_eq!(significance, [200, 100, 50, 10]);
+            assert_eq!(db.edge_list, [vec![1, 1], vec![0, 0, 2], vec![1], vec![]]);
+            assert_eq!(*db.embeddings.vector(3), [0.0, 0.0, 1.0]);
+            assert_eq!(db.headers[1].access_count, 1);
+            assert!(db.clusters.is_none());
+        }
+    }
+
+    #[test]
+    fn compressed_content_round_trips_in_less_heap() {
+        let document = "the quick brown fox jumps over the lazy dog. ".repeat(200);
+        let mut plain = memory_db();
+        add(&mut plain, &document, vec![1.0, 0.0]);
+        let mut compressed = memory_db();
+        compressed.set_compression_threshold(Some(1024));
+        let id = add(&mut compressed, &document, vec![1.0, 0.0]);
+
+        assert_eq!(compressed.get_node(id).unwrap(), document);
+        assert!(compressed.heap_size() < plain.heap_size() / 4);
+        // Small nodes stay uncompressed
+        let small = add(&mut compressed, "short", vec![0.0, 1.0]);
+        assert_eq!(compressed.headers[small as usize].flags & storage::FLAG_COMPRESSED, 0);
+    }
+
+    #[test]
+    fn baseline_v0_nodes_migrate_with_no_flags() {
+        let db = open_baseline();
+        assert!(db.headers.iter().all(|h| h.flags == 0));
+    }
+
+    #[test]
+    fn old_traffic_counts_less_than_recent_traffic() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        let old = add(&mut db, "old", vec![1.0, 0.0]);
+        let recent = add(&mut db, "recent", vec![0.0, 1.0]);
+        for _ in 0..50 {
+            db.get_node(old).unwrap();
+        }
+        let now = db.advance_clock(10 * 24 * 3600).unwrap();
+        for _ in 0..5 {
+            db.get_node(recent).unwrap();
+        }
+
+        let freshness = |id| db.bio_config.decayed_access(&db.header_view(id), now);
+        assert!(freshness(old) < freshness(recent), "{} vs {}", freshness(old), freshness(recent));
+        assert!(db.calculate_life_score(old).unwrap() < db.calculate_life_score(recent).unwrap());
+    }
+
+    #[test]
+    fn baseline_v0_accesses_migrate_as_recent_access() {
+        let db = open_baseline();
+        let recent: Vec<f32> = db.headers.iter().map(|h| h.recent_access).collect();
+        assert_eq!(recent, [0.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn expire_removes_nodes_past_their_ttl() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(1_000));
+        let session = db.add_node("session".to_string(), vec![1.0, 0.0], None, Some(2.0), Some(60), None).unwrap();
+        let durable = add(&mut db, "durable", vec![0.0, 1.0]);
+
+        assert!(db.expire().is_empty());
+        db.advance_clock(61).unwrap();
+        assert_eq!(db.expire(), [session]);
+        assert!(!db.headers[session as usize].is_live());
+        assert!(db.headers[durable as usize].is_live());
+    }
+
+    #[test]
+    fn baseline_v0_nodes_migrate_created_at_last_access_without_ttl() {
+        let mut db = open_baseline();
+        for h in &db.headers {
+            assert_eq!(h.created_ts, h.last_access_ts);
+            assert_eq!(h.ttl_secs, 0);
+        }
+        assert!(db.expire().is_empty());
+    }
+
+    #[test]
+    fn older_edge_contributes_less_graph_score() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        db.set_edge_decay(Some(24.0));
+        let old = add(&mut db, "old", vec![1.0, 0.0]);
+        let old_neighbor = add(&mut db, "old neighbor", vec![0.8, 0.6]);
+        db.add_edge(old, old_neighbor).unwrap();
+        let now = db.advance_clock(48 * 3600).unwrap();
+        let new = add(&mut db, "new", vec![1.0, 0.0]);
+        let new_neighbor = add(&mut db, "new neighbor", vec![0.8, 0.6]);
+        db.add_edge(new, new_neighbor).unwrap();
+
+        let graph_score = |id| ranking::calculate_graph_score(
+            id, &db.edge_list, &db.embeddings, &[], &[1.0, 0.0], &db.rank_config,
+            |a, b| db.edge_decay(a, b, now),
+        );
+        assert!(graph_score(old) < graph_score(new), "{} vs {}", graph_score(old), graph_score(new));
+    }
+
+    #[test]
+    fn baseline_v0_edges_migrate_created_with_their_later_endpoint() {
+        let db = open_baseline();
+        for (source, edges) in db.edge_list.iter().enumerate() {
+            assert_eq!(db.edge_created[source].len(), edges.len());
+            for (&target, &created) in edges.iter().zip(&db.edge_created[source]) {
+                let endpoints = db.headers[source].created_ts.max(db.headers[target as usize].created_ts);
+                assert_eq!(created, endpoints);
+            }
+        }
+        assert!(db.validate().is_ok());
+    }
+
+    #[test]
+    fn get_or_create_returns_existing_id_for_same_content() {
+        let mut db = memory_db();
+        let (id, created) = db.get_or_create("fact".to_string(), vec![1.0, 0.0], None).unwrap();
+        assert!(created);
+        assert_eq!(db.get_or_create("fact".to_string(), vec![0.0, 1.0], None).unwrap(), (id, false));
+        assert_eq!(db.headers.len(), 1);
+    }
+
+    #[test]
+    fn content_bytes_borrows_from_the_heap() {
+        let mut db = memory_db();
+        add(&mut db, "first", vec![1.0, 0.0]);
+        let id = add(&mut db, "second", vec![0.0, 1.0]);
+
+        let bytes = db.content_bytes(id).unwrap();
+        let heap: &[u8] = &db.data_heap;
+        let offset = db.headers[id as usize].data_offset as usize;
+        assert_eq!(bytes.as_ptr(), heap[offset..].as_ptr());
+        assert_eq!(db.content_str(id), Some("second"));
+    }
+
+    /// Ids of a search's results, best first.
+    fn ids(results: &[(u64, f32)]) -> Vec<u64> {
+        results.iter().map(|&(id, _)| id).collect()
+    }
+
+    #[test]
+    fn negative_similarity_ranks_below_positive() {
+        let mut db = memory_db();
+        let close = add(&mut db, "close", vec![1.0, 0.0]);
+        let related = add(&mut db, "related", vec![0.7, 0.7]);
+        let opposite = add(&mut db, "opposite", vec![-1.0, 0.1]);
+        db.add_node("significant opposite".to_string(), vec![-1.0, 0.2], Some(255.0), Some(2.0), None, None).unwrap();
+
+        let results = db.hybrid_search(vec![1.0, 0.0], 4, None, None, None, None, None).unwrap().into_scores();
+        let ranked = ids(&results);
+        assert_eq!(ranked[..2], [close, related]);
+        assert!(results.iter().all(|&(_, score)| score >= 0.0));
+        if let Some(pos) = ranked.iter().position(|&id| id == opposite) {
+            assert!(pos >= 2);
+        }
+    }
+
+    /// Root cluster over `members`, anchored at the first one.
+    fn cluster(id: u64, members: Vec<u64>) -> Cluster {
+        Cluster {
+            id,
+            anchor_node_id: members[0],
+            member_ids: members,
+            centroid: Vec::new(),
+            significance: 0.0,
+            sub_clusters: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    #[test]
+    fn vacuum_preserve_anchors_keeps_cold_anchor() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        let cold: Vec<u64> = (0..3)
+            .map(|i| db.add_node(format!("cold {}", i), vec![1.0, i as f32], Some(0.0), Some(2.0), None, None).unwrap())
+            .collect();
+        db.clusters = Some(vec![cluster(0, vec![cold[0], cold[1]])]);
+        db.advance_clock(30 * 24 * 3600).unwrap();
+
+        assert_eq!(db.vacuum(0.5), cold);
+        assert_eq!(db.vacuum_preserve_anchors(0.5), cold[1..]);
+    }
+
+    /// Nodes spread around the unit circle, for ranking tests.
+    fn fan_db(n: usize) -> SpiderDB {
+        let mut db = memory_db();
+        for i in 0..n {
+            let angle = i as f32 * 0.2;
+            add(&mut db, &format!("node {}", i), vec![angle.cos(), angle.sin()]);
+        }
+        db
+    }
+
+    #[test]
+    fn normalized_scores_are_in_unit_range_and_keep_order() {
+        let db = fan_db(8);
+        let raw = db.rank(&SearchQuery::new(vec![1.0, 0.0], 5)).unwrap();
+        let mut query = SearchQuery::new(vec![1.0, 0.0], 5);
+        query.normalize = true;
+        let normalized = db.rank(&query).unwrap();
+
+        assert_eq!(ids(&normalized), ids(&raw));
+        assert!(normalized.iter().all(|&(_, s)| (0.0..=1.0).contains(&s)));
+        assert_eq!(normalized[0].1, 1.0);
+        assert!(normalized.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    /// Path graph 0 - 1 - ... - (n-1).
+    fn path_db(n: usize) -> SpiderDB {
+        let mut db = memory_db();
+        for i in 0..n {
+            let angle = i as f32;
+            add(&mut db, &format!("node {}", i), vec![angle.cos(), angle.sin()]);
+            if i > 0 {
+                db.add_edge(i as u64 - 1, i as u64).unwrap();
+            }
+        }
+        db
+    }
+
+    #[test]
+    fn subgraph_keeps_only_induced_edges() {
+        let mut db = path_db(5);
+        db.add_edge(0, 4).unwrap();
+
+        let (nodes, edges) = db.subgraph(vec![1], 1);
+        assert_eq!(nodes, [0, 1, 2]);
+        assert_eq!(edges, [(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn in_band_search_skips_closer_out_of_band_nodes() {
+        let mut db = memory_db();
+        let add_sig = |db: &mut SpiderDB, angle: f32, significance: u8| {
+            db.add_node(format!("at {}", angle), vec![angle.cos(), angle.sin()], Some(significance as f32), Some(2.0), None, None).unwrap()
+        };
+        add_sig(&mut db, 0.0, 250);
+        add_sig(&mut db, 0.1, 10);
+        let in_band = add_sig(&mut db, 0.3, 100);
+        let in_band_far = add_sig(&mut db, 0.6, 120);
+
+        let results = db.hybrid_search_in_band(vec![1.0, 0.0], 4, 50, 200, None).unwrap();
+        assert_eq!(ids(&results), [in_band, in_band_far]);
+    }
+
+    #[test]
+    fn retention_hides_old_nodes_from_search_and_vacuums_them() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        let old = db.add_node("old".to_string(), vec![1.0, 0.0], Some(100.0), Some(2.0), None, None).unwrap();
+        let old_significant = db.add_node("old significant".to_string(), vec![0.9, 0.1], Some(250.0), Some(2.0), None, None).unwrap();
+        db.advance_clock(48 * 3600).unwrap();
+        let fresh = db.add_node("fresh".to_string(), vec![0.8, 0.2], Some(100.0), Some(2.0), None, None).unwrap();
+        db.set_retention_policy(Some(24.0), Some(200));
+
+        let found = ids(&db.rank(&SearchQuery::new(vec![1.0, 0.0], 3)).unwrap());
+        assert!(!found.contains(&old));
+        assert!(found.contains(&old_significant) && found.contains(&fresh));
+        assert_eq!(db.vacuum(0.0), [old]);
+    }
+
+    #[test]
+    fn small_database_returns_every_node_once() {
+        // HNSW alone misses a node on a few percent of tiny graphs
+        for _ in 0..50 {
+            let db = fan_db(3);
+            let results = db.hybrid_search(vec![1.0, 0.0], 10, None, None, None, None, None).unwrap().into_scores();
+            let unique: HashSet<u64> = ids(&results).into_iter().collect();
+            assert_eq!(results.len(), 3);
+            assert_eq!(unique.len(), 3);
+        }
+    }
+
+    /// Path for a scratch file unique to this test process.
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("spider-test-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn jsonl_export_round_trips() {
+        let db = path_db(3);
+        db.get_node(1).unwrap();
+        let path = temp_path("round-trip.jsonl");
+        assert_eq!(db.export_jsonl(path.clone()).unwrap(), 3);
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut copy = memory_db();
+        assert_eq!(copy.import_jsonl(path.clone()).unwrap(), 3);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines, 3);
+        for id in 0..3 {
+            assert_eq!(copy.content_str(id), db.content_str(id));
+            assert_eq!(copy.embeddings.vector(id as usize), db.embeddings.vector(id as usize));
+            assert_eq!(copy.edge_list[id as usize], db.edge_list[id as usize]);
+            assert_eq!(copy.header_view(id).access_count, db.header_view(id).access_count);
+        }
+    }
+
+    #[test]
+    fn omitted_significance_uses_the_default() {
+        let mut db = memory_db();
+        let id = add(&mut db, "plain", vec![1.0, 0.0]);
+        assert_eq!(db.headers[id as usize].significance, storage::DEFAULT_SIGNIFICANCE);
+
+        let mut db = SpiderDB::new(Some("/nonexistent/spider-test.db".to_string()), Some(8), None, None, Some(40)).unwrap();
+        let id = add(&mut db, "plain", vec![1.0, 0.0]);
+        assert_eq!(db.headers[id as usize].significance, 40);
+    }
+
+    #[test]
+    fn weight_as_cost_raises_on_anti_correlated_edges() {
+        let mut db = memory_db();
+        let a = add(&mut db, "a", vec![1.0, 0.0]);
+        let b = add(&mut db, "b", vec![-1.0, 0.1]);
+        db.add_edge(a, b).unwrap();
+
+        assert!(db.shortest_path_weighted(a, b, Some(true)).is_err());
+        let (path, _) = db.shortest_path_weighted(a, b, None).unwrap().unwrap();
+        assert_eq!(path, [a, b]);
+    }
+
+    #[test]
+    fn failures_report_their_error_variant() {
+        let mut db = memory_db();
+        add(&mut db, "a", vec![1.0, 0.0]);
+
+        assert!(matches!(db.header(9), Err(SpiderError::NodeNotFound(9))));
+        assert!(matches!(db.check_dimension(&[]), Err(SpiderError::EmptyEmbedding)));
+        assert!(matches!(
+            db.check_dimension(&[1.0, 0.0, 0.0]),
+            Err(SpiderError::DimensionMismatch { expected: 2, got: 3 })
+        ));
+        assert!(matches!(db.check_query(&[0.0, 0.0]), Err(SpiderError::ZeroQuery)));
+        assert!(matches!(
+            storage::significance_from_scale(11.0, 10.0),
+            Err(SpiderError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn logical_clock_drives_vacuum() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        let read = add(&mut db, "read", vec![1.0, 0.0]);
+        let idle = add(&mut db, "idle", vec![0.0, 1.0]);
+        let threshold = db.calculate_life_score(idle).unwrap() / 4.0;
+        assert!(db.vacuum(threshold).is_empty());
+
+        // Only the clock ages nodes, so the outcome doesn't depend on wall time
+        db.advance_clock(3600).unwrap();
+        assert!(db.vacuum(threshold).is_empty());
+        db.advance_clock(3 * 3600).unwrap();
+        db.get_node(read).unwrap();
+        assert_eq!(db.vacuum(threshold), [idle]);
+    }
+
+    #[test]
+    fn merge_nodes_combines_neighbors_and_removes_duplicates() {
+        let mut db = path_db(5);
+        // 1 and 3 duplicate each other: 1 has neighbors 0, 2; 3 has 2, 4
+        assert_eq!(db.merge_nodes(1, vec![3]).unwrap(), 1);
+
+        let mut neighbors = db.get_neighbors(1).unwrap();
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, [0, 2, 4]);
+        assert!(db.header(3).is_err());
+        assert!(!db.get_neighbors(4).unwrap().contains(&3));
+        assert!(db.validate().is_ok());
+    }
+
+    /// Runs `f` with the GIL held, starting the interpreter if needed.
+    fn with_py<R>(f: impl FnOnce(Python<'_>) -> R) -> R {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f)
+    }
+
+    #[test]
+    fn build_clusters_reports_progress() {
+        let mut db = fan_db(12);
+        with_py(|py| {
+            let calls = pyo3::types::PyList::empty(py);
+            let callback = py.eval("lambda calls: lambda done, total: calls.append((done, total))", None, None)
+                .unwrap()
+                .call1((calls,))
+                .unwrap();
+            db.build_clusters(py, Some(2), Some(callback.into())).unwrap();
+
+            let calls: Vec<(usize, usize)> = calls.extract().unwrap();
+            assert!(!calls.is_empty());
+            assert_eq!(calls[0].0, 1);
+            assert!(calls.iter().all(|&(done, total)| done <= total));
+        });
+        assert!(db.clusters.is_some());
+    }
+
+    #[test]
+    fn build_clusters_stops_when_progress_raises() {
+        let mut db = fan_db(12);
+        with_py(|py| {
+            let callback = py.eval("lambda done, total: 1 / 0", None, None).unwrap();
+            let err = db.build_clusters(py, Some(2), Some(callback.into())).unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyZeroDivisionError>(py));
+        });
+        assert!(db.clusters.is_none());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn to_arrow_has_a_row_per_live_node() {
+        use arrow::array::{StringArray, UInt8Array};
+
+        let mut db = path_db(4);
+        db.delete_nodes(&HashSet::from([2]));
+        let batch = db.to_arrow().unwrap();
+
+        assert_eq!(batch.num_rows(), 3);
+        let content = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(content.value(2), "node 3");
+        let significance = batch.column(2).as_any().downcast_ref::<UInt8Array>().unwrap();
+        assert_eq!(significance.value(0), storage::DEFAULT_SIGNIFICANCE);
+    }
+
+    #[test]
+    fn index_grows_past_its_initial_capacity() {
+        let mut db = SpiderDB::new(Some("/nonexistent/spider-test.db".to_string()), Some(4), None, None, None).unwrap();
+        for i in 0..12 {
+            let angle = i as f32 * 0.5;
+            add(&mut db, &format!("node {}", i), vec![angle.cos(), angle.sin(), 0.1]);
+        }
+
+        let (indexed, capacity) = db.index_capacity();
+        assert_eq!(indexed, 12);
+        assert!(capacity >= 12, "capacity {}", capacity);
+        // Query the index itself, not `nearest`, which would mask a miss
+        for (id, embedding) in db.embeddings.to_vecs().iter().enumerate() {
+            let hits = db.index.search(embedding, 1, None);
+            assert_eq!(ids(&hits), vec![id as u64]);
+        }
+    }
+
+    #[test]
+    fn verified_search_matches_exact_order() {
+        let db = fan_db(10);
+        let query = vec![0.3f32.cos(), 0.3f32.sin()];
+
+        // Oversampling covers every node, so the candidate set is the whole database
+        let verified = db.hybrid_search_verified(query.clone(), 4, Some(3), None);
+        let exact = with_py(|py| db.exact_search(py, query, 4)).unwrap();
+        assert_eq!(ids(&verified), ids(&exact));
+        for ((_, a), (_, b)) in verified.iter().zip(&exact) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn l2_metric_ranks_by_distance() {
+        let mut db = fan_db(6);
+        // Same direction as node 0 (a cosine tie) but far from it in L2
+        let far: Vec<f32> = db.embeddings.vector(0).iter().map(|x| x * 3.0).collect();
+        add(&mut db, "far", far);
+        db.set_metric("l2").unwrap();
+
+        let results = db.hybrid_search(db.embeddings.vector(0).into_owned(), 3, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(ids(&results), vec![0, 1, 2]);
+        assert!(results.windows(2).all(|w| w[0].1 > w[1].1), "{:?}", results);
+        assert!(results.iter().all(|&(_, score)| (0.0..=1.0).contains(&score)), "{:?}", results);
+    }
+
+    #[test]
+    fn text_search_matches_the_direct_embedding() {
+        let query = vec![0.5f32.cos(), 0.5f32.sin()];
+        // Searching bumps access counts, so each path gets its own copy of the data
+        let direct = fan_db(8).hybrid_search(query.clone(), 3, None, None, None, None, None).unwrap().into_scores();
+
+        with_py(|py| {
+            let embed_fn = py.eval(&format!("lambda text: {:?}", query), None, None).unwrap().to_object(py);
+            let via_text = fan_db(8).hybrid_search_text(py, "anything".to_string(), 3, embed_fn, None).unwrap();
+            assert_eq!(ids(&via_text), ids(&direct));
+            for ((_, a), (_, b)) in via_text.iter().zip(&direct) {
+                assert!((a - b).abs() < 1e-6);
+            }
+
+            let wrong_dim = py.eval("lambda text: [1.0, 0.0, 0.0]", None, None).unwrap().to_object(py);
+            let err = fan_db(8).hybrid_search_text(py, "anything".to_string(), 3, wrong_dim, None).unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn compact_steps_reclaim_what_compact_would() {
+        let build = || {
+            let mut db = path_db(8);
+            db.delete_nodes(&HashSet::from([1, 4, 5]));
+            db
+        };
+        let mut full = build();
+        full.compact();
+
+        let mut stepped = build();
+        let dead = stepped.dead_bytes();
+        assert!(dead > 0);
+        let mut reclaimed = 0;
+        for _ in 0..100 {
+            if stepped.dead_bytes() == 0 { break; }
+            reclaimed += stepped.compact_step(6);
+        }
+
+        assert_eq!(reclaimed, dead);
+        assert_eq!(stepped.heap_size(), full.heap_size());
+        for id in [0, 2, 3, 6, 7] {
+            assert_eq!(content_str(&stepped, id), format!("node {}", id));
+        }
+    }
+
+    #[test]
+    fn significance_boost_injects_missed_nodes() {
+        let mut db = fan_db(5);
+        let important = db.add_node("important".to_string(), vec![0.1f32.cos(), 0.1f32.sin()], Some(255.0), Some(2.0), None, None).unwrap();
+        // A hierarchy built before the node arrived can't surface it
+        let mut stale = cluster(0, vec![0, 1, 2, 3, 4]);
+        stale.centroid = vec![1.0, 0.0];
+        db.clusters = Some(vec![stale]);
+        let query = vec![0.1f32.cos(), 0.1f32.sin()];
+
+        let plain = db.hybrid_search(query.clone(), 3, None, None, None, None, None).unwrap().into_scores();
+        assert!(!ids(&plain).contains(&important));
+
+        db.set_significance_boost(Some(200));
+        let boosted = db.hybrid_search(query, 3, None, None, None, None, None).unwrap().into_scores();
+        assert!(ids(&boosted).contains(&important), "{:?}", boosted);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_export_reads_back() {
+        use arrow::array::StringArray;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = temp_path("export.parquet");
+        let db = path_db(5);
+        assert_eq!(db.export_parquet(path.clone()).unwrap(), 5);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap().build().unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+        let content = batches[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(content.value(3), "node 3");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn filtered_search_overfetches_to_k() {
+        let mut db = memory_db();
+        // 30 near neighbors, only every tenth in band; then 10 farther in-band nodes
+        for i in 0..40 {
+            let (angle, significance) = if i < 30 {
+                (i as f32 * 0.01, if i % 10 == 0 { 100 } else { 10 })
+            } else {
+                (0.2 + i as f32 * 0.01, 100)
+            };
+            db.add_node(format!("node {}", i), vec![angle.cos(), angle.sin()], Some(significance as f32), Some(2.0), None, None).unwrap();
+        }
+        let query = vec![1.0, 0.0];
+
+        let results = db.hybrid_search_in_band(query.clone(), 5, 50, 200, None).unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(ids(&results).iter().all(|&id| db.headers[id as usize].significance == 100));
+
+        db.set_adaptive_overfetch(false);
+        let results = db.hybrid_search_in_band(query, 5, 50, 200, None).unwrap();
+        assert!(results.len() < 5, "{:?}", results);
+    }
+
+    #[test]
+    fn concurrent_reads_count_every_access() {
+        let db = path_db(3);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..500 {
+                        db.get_node(1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(db.header_view(1).access_count, 8 * 500);
+        assert_eq!(db.header_view(0).access_count, 0);
+    }
+
+    #[test]
+    fn knn_export_writes_exact_similarities() {
+        let path = temp_path("knn.csv");
+        let db = fan_db(6);
+        let written = db.export_knn_graph(2, path.clone(), Some(0.5), None).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("src,dst,similarity"));
+        let rows: Vec<(u64, u64, f32)> = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                (fields[0].parse().unwrap(), fields[1].parse().unwrap(), fields[2].parse().unwrap())
+            })
+            .collect();
+        assert_eq!(rows.len(), written);
+        // Every node's two fan neighbors lie within 0.4 rad, well above 0.5
+        assert_eq!(written, 6 * 2);
+        for src in 0..6 {
+            assert!(rows.iter().filter(|r| r.0 == src).count() <= 2);
+        }
+        for &(src, dst, sim) in &rows {
+            assert_ne!(src, dst);
+            let exact = search::cosine_similarity(&db.embeddings.vector(src as usize), &db.embeddings.vector(dst as usize));
+            assert!((sim - exact).abs() < 1e-6 && sim >= 0.5);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pinned_node_survives_vacuum_and_expiry() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        let cold: Vec<u64> = (0..2)
+            .map(|i| db.add_node(format!("cold {}", i), vec![1.0, i as f32], Some(0.0), Some(2.0), Some(60), None).unwrap())
+            .collect();
+        db.pin(cold[0]).unwrap();
+        db.advance_clock(30 * 24 * 3600).unwrap();
+
+        assert_eq!(db.vacuum(0.5), [cold[1]]);
+        assert_eq!(db.expire(), [cold[1]]);
+        assert_eq!(content_str(&db, cold[0]), "cold 0");
+
+        db.unpin(cold[0]).unwrap();
+        assert_eq!(db.vacuum(0.5), [cold[0]]);
+    }
+
+    #[test]
+    fn excluded_nodes_never_come_back() {
+        let db = fan_db(10);
+        // 0 and 1 are the closest to the query
+        let exclude = vec![0, 1];
+        let results = db.hybrid_search_excluding(vec![1.0, 0.0], 4, exclude.clone(), None).unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert!(ids(&results).iter().all(|id| !exclude.contains(id)), "{:?}", results);
+        assert_eq!(ids(&results)[0], 2);
+    }
+
+    #[test]
+    fn consolidate_replaces_qualifying_nodes_with_a_summary() {
+        let mut db = memory_db();
+        let mut add_sig = |angle: f32, significance: u8| {
+            db.add_node(format!("at {}", angle), vec![angle.cos(), angle.sin()], Some(significance as f32), Some(2.0), None, None).unwrap()
+        };
+        let similar = [add_sig(0.0, 10), add_sig(0.05, 10), add_sig(0.1, 10)];
+        let important = add_sig(0.02, 200);
+        let unrelated = add_sig(1.5, 10);
+
+        with_py(|py| {
+            let failing = py.eval("lambda contents: 1 / 0", None, None).unwrap().to_object(py);
+            assert!(db.consolidate(py, 0.9, 50, Some(failing)).is_err());
+            assert_eq!(db.headers.iter().filter(|h| h.is_live()).count(), 5);
+
+            let summarize = py.eval("lambda contents: 'summary of %d' % len(contents)", None, None).unwrap().to_object(py);
+            let summaries = db.consolidate(py, 0.9, 50, Some(summarize)).unwrap();
+            assert_eq!(summaries.len(), 1);
+            assert_eq!(content_str(&db, summaries[0]), "summary of 3");
+        });
+        assert!(similar.iter().all(|&id| db.header(id).is_err()));
+        assert_eq!(content_str(&db, important), "at 0.02");
+        assert_eq!(content_str(&db, unrelated), "at 1.5");
+    }
+
+    #[test]
+    fn preview_is_a_utf8_prefix_and_not_an_access() {
+        let mut db = memory_db();
+        let content = "héllo wörld, ünïcode";
+        let id = add(&mut db, content, vec![1.0, 0.0]);
+
+        for max_bytes in 0..=content.len() + 3 {
+            let preview = db.content_preview(id, max_bytes).unwrap();
+            assert!(content.starts_with(&preview) && preview.len() <= max_bytes, "{:?}", preview);
+        }
+        // 'é' spans bytes 1..3, so a 2-byte cut backs off to "h"
+        assert_eq!(db.content_preview(id, 2).unwrap(), "h");
+        assert_eq!(db.header_view(id).access_count, 0);
+        assert_eq!(db.content_preview(99, 4), None);
+    }
+
+    #[test]
+    fn edge_count_counts_distinct_edges() {
+        let mut db = path_db(5);
+        assert_eq!(db.edge_count_total(), 4);
+
+        db.add_edge(3, 2).unwrap(); // Same edge again, other direction
+        db.add_edge(0, 4).unwrap();
+        db.set_self_loops("allow").unwrap();
+        db.add_edge(1, 1).unwrap();
+        assert_eq!(db.edge_count_total(), 6);
+    }
+
+    #[test]
+    fn exact_search_matches_a_full_sort() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut db = memory_db();
+        for i in 0..300 {
+            add(&mut db, &format!("node {}", i), (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        }
+        db.delete_nodes(&(0..300).step_by(7).collect());
+        let query: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+        let mut all: Vec<(u64, f32)> = db.headers.iter()
+            .filter(|h| h.is_live())
+            .map(|h| (h.id, search::cosine_similarity(&query, &db.embeddings.vector(h.id as usize))))
+            .collect();
+        all.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        all.truncate(25);
+        assert_eq!(with_py(|py| db.exact_search(py, query, 25)).unwrap(), all);
+    }
+
+    #[test]
+    fn compact_maps_survivors_and_drops_removed_ids() {
+        let mut db = path_db(6);
+        db.delete_nodes(&HashSet::from([1, 4]));
+        let id_map = db.compact();
+
+        assert_eq!(id_map, HashMap::from([(0, 0), (2, 1), (3, 2), (5, 3)]));
+        for (&old, &new) in &id_map {
+            assert_eq!(content_str(&db, new), format!("node {}", old));
+        }
+        // The 2-3 edge survives under the new ids
+        assert_eq!(db.edge_list[1], [2]);
+    }
+
+    #[test]
+    fn search_results_carry_a_cluster_containing_them() {
+        let mut db = fan_db(12);
+        let near = add(&mut db, "near the query", vec![1.0, 0.01]);
+        with_py(|py| db.build_clusters(py, Some(3), None)).unwrap();
+        let late = add(&mut db, "added after build", vec![1.0, 0.0]);
+
+        let results = db.hybrid_search_with_clusters(vec![1.0, 0.0], 5, None).unwrap();
+        assert!(results.iter().any(|&(id, _, _)| id == near));
+        for &(id, _, cluster_id) in &results {
+            match cluster_id {
+                Some(cid) => {
+                    let cluster = crate::cluster::find_cluster(db.clusters.as_ref().unwrap(), cid).unwrap();
+                    assert!(cluster.member_ids.contains(&id), "{} not in cluster {}", id, cid);
+                }
+                // Only a node the hierarchy predates may lack a cluster
+                None => assert_eq!(id, late),
+            }
+        }
+    }
+
+    #[test]
+    fn softmax_scores_sum_to_one_in_raw_order() {
+        let query = vec![0.3f32.cos(), 0.3f32.sin()];
+        let raw = fan_db(10).hybrid_search(query.clone(), 5, None, None, None, None, None).unwrap().into_scores();
+        let soft = fan_db(10).hybrid_search(query, 5, None, None, Some(0.1), None, None).unwrap().into_scores();
+
+        assert_eq!(ids(&soft), ids(&raw));
+        assert!(soft.iter().all(|&(_, p)| p >= 0.0));
+        assert!((soft.iter().map(|&(_, p)| p).sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(soft.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn bulk_edge_removal_keeps_unrelated_edges() {
+        let mut db = path_db(5);
+        db.add_edge(0, 4).unwrap();
+
+        // (2, 1) is (1, 2) reversed; (3, 3) and (0, 2) were never linked
+        let removed = db.remove_edges_bulk(vec![(2, 1), (1, 2), (3, 4), (3, 3), (0, 2)]);
+        assert_eq!(removed, 2);
+        assert_eq!(db.edge_list, [vec![1, 4], vec![0], vec![3], vec![2], vec![0]]);
+        assert!(db.headers.iter().all(|h| h.edge_count as usize == db.edge_list[h.id as usize].len()));
+    }
+
+    #[test]
+    fn validate_reports_a_corrupt_offset() {
+        let mut db = path_db(4);
+        assert_eq!(db.validate(), Ok(()));
+
+        db.headers[2].data_offset = 1_000;
+        let errors = db.validate().unwrap_err();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].starts_with("node 2 content"), "{:?}", errors);
+    }
+
+    #[test]
+    fn isolated_nodes_lists_only_unlinked_nodes() {
+        let mut db = path_db(3);
+        let lonely = add(&mut db, "lonely", vec![0.0, 1.0]);
+        let gone = add(&mut db, "gone", vec![0.0, 1.0]);
+        db.delete_nodes(&HashSet::from([gone]));
+
+        assert_eq!(db.isolated_nodes(), [lonely]);
+    }
+
+    #[test]
+    fn cluster_guided_search_matches_global_top_hits() {
+        let mut db = grouped_db();
+        with_py(|py| db.build_clusters(py, Some(3), None)).unwrap();
+        assert_eq!(db.clusters.as_ref().unwrap().len(), 3);
+
+        let query = vec![0.1, 1.0, 0.05];
+        let guided = db.cluster_guided_search(query.clone(), 3, Some(1));
+        let global = with_py(|py| db.exact_search(py, query, 3)).unwrap();
+        assert_eq!(guided, global);
+    }
+
+    #[test]
+    fn text_search_finds_only_matching_nodes() {
+        let mut db = memory_db();
+        let hit = add(&mut db, "request failed with E1042 after retry", vec![1.0, 0.0]);
+        add(&mut db, "request failed with E1043", vec![1.0, 0.0]);
+        let also = add(&mut db, "E1042", vec![1.0, 0.0]);
+        let deleted = add(&mut db, "E1042 again", vec![1.0, 0.0]);
+        db.delete_nodes(&HashSet::from([deleted]));
+
+        assert_eq!(db.search_text("E1042".to_string(), None, None, None), [hit, also]);
+        assert_eq!(db.search_text("E1042".to_string(), Some(1), None, None), [hit]);
+        assert!(db.search_text("e1042".to_string(), None, None, None).is_empty());
+        assert_eq!(db.header_view(hit).access_count, 0);
+    }
+
+    #[test]
+    fn text_search_case_and_whole_word_modes() {
+        let mut db = memory_db();
+        let mixed = add(&mut db, "an ERROR occurred", vec![1.0, 0.0]);
+        let plural = add(&mut db, "two Errors logged", vec![1.0, 0.0]);
+        let exact = add(&mut db, "Error: disk full", vec![1.0, 0.0]);
+        let search = |case_insensitive, whole_word| {
+            db.search_text("Error".to_string(), None, Some(case_insensitive), Some(whole_word))
+        };
+
+        assert_eq!(search(false, false), [plural, exact]);
+        assert_eq!(search(true, false), [mixed, plural, exact]);
+        assert_eq!(search(false, true), [exact]);
+        assert_eq!(search(true, true), [mixed, exact]);
+    }
+
+    #[test]
+    fn keyword_match_outranks_paraphrase_at_high_lexical_weight() {
+        let mut db = memory_db();
+        let paraphrase = add(&mut db, "the storage device ran out of room", vec![1.0, 0.0]);
+        let keyword = add(&mut db, "disk full on volume", vec![0.2f32.cos(), 0.2f32.sin()]);
+        let search = |weight| {
+            db.search_hybrid_lexical("disk full".to_string(), vec![1.0, 0.0], 2, Some(weight)).unwrap()
+        };
+
+        assert_eq!(ids(&search(0.0)), [paraphrase, keyword]);
+        assert_eq!(ids(&search(0.6)), [keyword, paraphrase]);
+    }
+
+    #[test]
+    fn neighbors_survive_save_and_load() {
+        let path = temp_path("neighbors.db");
+        let mut db = path_db(5);
+        db.add_edge(0, 3).unwrap();
+        db.remove_edges_bulk(vec![(1, 2)]);
+        db.save(Some(path.clone())).unwrap();
+
+        let loaded = SpiderDB::new(Some(path.clone()), Some(64), None, None, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for id in 0..5 {
+            let sorted = |mut n: Vec<u64>| { n.sort(); n };
+            assert_eq!(sorted(loaded.get_neighbors(id).unwrap()), sorted(db.get_neighbors(id).unwrap()), "node {}", id);
+        }
+        assert_eq!(loaded.validate(), Ok(()));
+    }
+
+    #[test]
+    fn dedup_keeps_one_node_per_content() {
+        let mut db = memory_db();
+        let first = add(&mut db, "same text", vec![1.0, 0.0]);
+        let twin = add(&mut db, "same text", vec![1.0, 0.01]);
+        add(&mut db, "other text", vec![0.3f32.cos(), 0.3f32.sin()]);
+        add(&mut db, "third text", vec![0.5f32.cos(), 0.5f32.sin()]);
+
+        let plain = db.hybrid_search(vec![1.0, 0.0], 3, None, None, None, None, None).unwrap().into_scores();
+        assert!(ids(&plain).contains(&first) && ids(&plain).contains(&twin));
+
+        let deduped = db.hybrid_search(vec![1.0, 0.0], 3, None, None, None, Some(true), None).unwrap().into_scores();
+        assert_eq!(deduped.len(), 3);
+        assert_eq!(ids(&deduped).iter().filter(|&&id| id == first || id == twin).count(), 1);
+    }
+
+    /// Three tight groups of five around the axes of 3-D space.
+    fn grouped_db() -> SpiderDB {
+        let mut db = memory_db();
+        for (g, direction) in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].iter().enumerate() {
+            for i in 0..5 {
+                let jitter = i as f32 * 0.05;
+                add(&mut db, &format!("group {} #{}", g, i), vec![direction[0] + jitter, direction[1] + jitter, direction[2]]);
+            }
+        }
+        db
+    }
+
+    #[test]
+    fn prepare_leaves_the_database_ready_to_search() {
+        let mut db = grouped_db();
+        with_py(|py| db.prepare(py, Some(3), Some(8), Some(100), None)).unwrap();
+
+        assert_eq!(db.clusters.as_ref().map(Vec::len), Some(3));
+        assert_eq!(db.index_capacity().0, 15);
+        let results = db.hybrid_search(vec![0.0, 0.1, 1.0], 5, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(results.len(), 5);
+        assert!(ids(&results).iter().all(|&id| (10..15).contains(&id)), "{:?}", results);
+    }
+
+    #[test]
+    fn stored_clusters_feed_the_ranking() {
+        let mut db = grouped_db();
+        with_py(|py| db.build_clusters(py, Some(3), None)).unwrap();
+        let query = SearchQuery::new(vec![1.0, 0.05, 0.0], 3);
+        let clustered = db.rank(&query).unwrap();
+
+        db.clear_clusters();
+        let unclustered = db.rank(&query).unwrap();
+        assert_eq!(ids(&clustered), ids(&unclustered));
+        // Only the cluster term differs, and it's positive for members of the matching cluster
+        for ((_, with), (_, without)) in clustered.iter().zip(&unclustered) {
+            assert!(with > without, "{} vs {}", with, without);
+        }
+    }
+
+    #[test]
+    fn recluster_waits_for_enough_drift() {
+        let mut db = grouped_db();
+        with_py(|py| {
+            db.build_clusters(py, Some(3), None).unwrap();
+
+            add(&mut db, "one more", vec![1.0, 0.1, 0.0]);
+            assert!(!db.maybe_recluster(py, 0.25).unwrap());
+
+            for i in 0..5 {
+                add(&mut db, &format!("late {}", i), vec![0.0, 1.0, 0.1 * i as f32]);
+            }
+            assert!(db.maybe_recluster(py, 0.25).unwrap());
+            // The rebuild resets the drift
+            assert!(!db.maybe_recluster(py, 0.25).unwrap());
+        });
+        let clustered: usize = db.clusters.as_ref().unwrap().iter().map(|c| c.member_ids.len()).sum();
+        assert_eq!(clustered, 21);
+    }
+
+    #[test]
+    fn recluster_drift_ignores_deleted_nodes() {
+        let mut db = grouped_db();
+        with_py(|py| {
+            db.build_clusters(py, Some(3), None).unwrap();
+            db.delete_nodes(&(0..10).collect());
+
+            // 2 inserts against 7 live nodes, though 17 headers exist
+            add(&mut db, "late 0", vec![0.0, 0.0, 1.0]);
+            add(&mut db, "late 1", vec![0.0, 0.1, 1.0]);
+            assert!(db.maybe_recluster(py, 0.25).unwrap());
+        });
+    }
+
+    #[test]
+    fn access_report_reflects_reads() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(100));
+        let (a, b) = (add(&mut db, "a", vec![1.0, 0.0]), add(&mut db, "b", vec![0.0, 1.0]));
+        db.advance_clock(50).unwrap();
+        db.get_node(b).unwrap();
+        db.get_node(b).unwrap();
+
+        assert_eq!(db.access_report(), [(a, 0, 100), (b, 2, 150)]);
+    }
+
+    #[test]
+    fn f64_embeddings_are_stored_at_f32_precision() {
+        let mut db = memory_db();
+        let precise = vec![0.1f64, 0.7000000000000001, 1.0 / 3.0];
+        let id = db.add_node_f64("f64".to_string(), precise.clone(), None, Some(2.0), None, None).unwrap();
+        add(&mut db, "other", vec![0.0, 0.0, 1.0]);
+
+        let stored: Vec<f32> = precise.iter().map(|&x| x as f32).collect();
+        assert_eq!(db.embeddings.vector(id as usize), stored);
+        let hits = db.hybrid_search_verified(stored, 1, None, None);
+        assert_eq!(ids(&hits), [id]);
+        assert!((hits[0].1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fetch_matches_the_individual_accessors() {
+        let mut db = memory_db();
+        let id = db.add_node("fetched".to_string(), vec![0.6, 0.8], Some(200.0), Some(2.0), None, None).unwrap();
+        db.get_node(id).unwrap();
+
+        let record = db.fetch(id, Some(true)).unwrap().unwrap();
+        let (_, access_count, last_access_ts) = db.access_report()[0];
+        assert_eq!(record.content, content_str(&db, id));
+        assert_eq!(record.embedding.as_deref(), Some(&db.embeddings.vector(id as usize)[..]));
+        assert_eq!(record.significance, 200);
+        assert_eq!((record.access_count, record.last_access_ts), (access_count, last_access_ts));
+        assert_eq!(record.life_score, db.calculate_life_score(id).unwrap());
+
+        // Fetching is not an access, and the embedding is opt-in
+        let again = db.fetch(id, None).unwrap().unwrap();
+        assert_eq!((again.access_count, again.embedding), (1, None));
+        assert!(db.fetch(7, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn fresh_node_surfaces_among_older_read_ones() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        for i in 0..6 {
+            let angle = i as f32 * 0.2;
+            let id = add(&mut db, &format!("node {}", i), vec![angle.cos(), angle.sin()]);
+            for _ in 0..3 {
+                db.get_node(id).unwrap();
+            }
+        }
+        db.advance_clock(48 * 3600).unwrap();
+        let fresh = add(&mut db, "fresh", vec![0.5f32.cos(), 0.5f32.sin()]);
+
+        // Unread but default-significance, it scores well above the default floor
+        let life = db.calculate_life_score(fresh).unwrap();
+        assert!(life > db.bio_config.min_life_score + 100.0, "{}", life);
+        let results = db.hybrid_search(vec![0.5f32.cos(), 0.5f32.sin()], 3, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(ids(&results)[0], fresh, "{:?}", results);
+    }
+
+    #[test]
+    fn sampling_favors_significant_nodes() {
+        let mut db = memory_db();
+        for i in 0..40 {
+            let significance = if i < 10 { 250 } else { 10 };
+            db.add_node(format!("node {}", i), vec![1.0, i as f32], Some(significance as f32), Some(2.0), None, None).unwrap();
+        }
+
+        let draws: Vec<u64> = (0..200).flat_map(|seed| db.representative_sample(10, seed)).collect();
+        let significant = draws.iter().filter(|&&id| id < 10).count() as f32 / draws.len() as f32;
+        // Uniform sampling would give a quarter
+        assert!(significant > 0.6, "{}", significant);
+        assert_eq!(db.representative_sample(10, 7), db.representative_sample(10, 7));
+        assert_eq!(db.representative_sample(100, 7).len(), 40);
+    }
+
+    #[test]
+    fn diff_reports_an_added_node_and_edge() {
+        let path = temp_path("diff-before.db");
+        let mut db = path_db(3);
+        db.save(Some(path.clone())).unwrap();
+        let before = SpiderDB::new(Some(path.clone()), Some(64), None, None, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let extra = add(&mut db, "extra", vec![0.0, 1.0]);
+        db.add_edge(0, extra).unwrap();
+        let diff = before.diff(&db);
+
+        assert_eq!(diff.added, [extra]);
+        assert_eq!(diff.edges_added, [(0, extra)]);
+        assert!(diff.removed.is_empty() && diff.edges_removed.is_empty() && diff.significance_changed.is_empty());
+        assert!(db.diff(&db).added.is_empty());
+    }
+
+    #[test]
+    fn graph_labels_are_cut_to_preview_len() {
+        let mut db = memory_db();
+        add(&mut db, "héllo wörld", vec![1.0, 0.0]);
+        add(&mut db, "ünï", vec![0.0, 1.0]);
+        let (nodes, _) = db.get_all_graph_data(Some(4));
+        let labels: Vec<&str> = nodes.iter().map(|n| n.1.as_str()).collect();
+        assert_eq!(labels, ["héll…", "ünï"]);
+        let (nodes, _) = db.get_all_graph_data(None);
+        assert_eq!(nodes[0].1, "héllo wörld");
+    }
+
+    #[test]
+    fn two_triangles_joined_by_an_edge_have_one_bridge() {
+        let mut db = memory_db();
+        for i in 0..6 {
+            let angle = i as f32;
+            add(&mut db, &format!("node {}", i), vec![angle.cos(), angle.sin()]);
+        }
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)] {
+            db.add_edge(a, b).unwrap();
+        }
+        assert_eq!(db.articulation_points(), vec![2, 3]);
+        assert_eq!(db.bridges(), vec![(2, 3)]);
+    }
+
+    #[test]
+    fn search_query_builder_matches_the_direct_calls() {
+        let build = || {
+            let mut db = memory_db();
+            db.enable_logical_clock(Some(1_000));
+            for i in 0..8 {
+                let angle = i as f32 * 0.2;
+                add(&mut db, &format!("node {}", i % 6), vec![angle.cos(), angle.sin()]);
+            }
+            db
+        };
+        let query = vec![1.0, 0.1];
+
+        with_py(|py| {
+            let built = Py::new(py, SearchQuery::new(query.clone(), 10)).unwrap();
+            built.call_method1(py, "k", (3,)).unwrap()
+                .call_method1(py, "ef_search", (32,)).unwrap()
+                .call_method1(py, "normalize", (true,)).unwrap()
+                .call_method1(py, "dedup_by_content", (true,)).unwrap();
+            let built = built.borrow(py).clone();
+            let via_builder = build().run(&built).unwrap().into_scores();
+            let direct = build().hybrid_search(query.clone(), 3, Some(32), Some(true), None, Some(true), None).unwrap().into_scores();
+            assert_eq!(via_builder.len(), 3);
+            assert_eq!(via_builder, direct);
+
+            let built = Py::new(py, SearchQuery::new(query.clone(), 4)).unwrap();
+            built.call_method1(py, "exclude", (vec![0u64, 1],)).unwrap();
+            let built = built.borrow(py).clone();
+            let via_builder = build().run(&built).unwrap().into_scores();
+            let direct = build().hybrid_search_excluding(query.clone(), 4, vec![0, 1], None).unwrap();
+            assert!(via_builder.iter().all(|&(id, _)| id > 1));
+            assert_eq!(via_builder, direct);
+        });
+    }
+
+    #[test]
+    fn exact_search_stops_at_an_interrupt_between_chunks() {
+        let db = fan_db(20);
+        with_py(|py| {
+            let mut checks = 0;
+            let result = db.exact_search_chunked(py, &[1.0, 0.0], 3, 4, |_| {
+                checks += 1;
+                if checks == 3 {
+                    return Err(pyo3::exceptions::PyKeyboardInterrupt::new_err(()));
+                }
+                Ok(())
+            });
+            assert!(result.unwrap_err().is_instance_of::<pyo3::exceptions::PyKeyboardInterrupt>(py));
+            assert_eq!(checks, 3);
+
+            let chunked = db.exact_search_chunked(py, &[1.0, 0.0], 3, 4, |_| Ok(())).unwrap();
+            assert_eq!(chunked, db.exact_search(py, vec![1.0, 0.0], 3).unwrap());
+            assert_eq!(ids(&chunked), vec![0, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn life_score_forecast_decreases_without_access() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(1_000));
+        let id = add(&mut db, "fading", vec![1.0, 0.0]);
+        db.get_node(id).unwrap();
+        db.get_node(id).unwrap();
+
+        let hours = vec![0, 1, 6, 24, 72, 168, 720];
+        let forecast = db.life_score_forecast(id, hours.clone()).unwrap();
+        assert_eq!(forecast.iter().map(|&(h, _)| h).collect::<Vec<_>>(), hours);
+        assert_eq!(forecast[0].1, db.calculate_life_score(id).unwrap());
+        assert!(forecast.windows(2).all(|w| w[1].1 < w[0].1), "{:?}", forecast);
+        assert!(db.life_score_forecast(99, vec![1]).is_err());
+    }
+
+    #[test]
+    fn self_loops_follow_the_configured_mode() {
+        let mut db = memory_db();
+        let a = add(&mut db, "a", vec![1.0, 0.0]);
+        let b = add(&mut db, "b", vec![0.0, 1.0]);
+        db.add_edge(a, b).unwrap();
+
+        // Default drops them: degree is untouched
+        db.add_edge(a, a).unwrap();
+        assert_eq!(db.get_neighbors(a).unwrap(), vec![b]);
+        assert_eq!(db.headers[a as usize].edge_count, 1);
+
+        db.set_self_loops("reject").unwrap();
+        assert!(db.add_edge(a, a).is_err());
+        assert_eq!(db.headers[a as usize].edge_count, 1);
+
+        db.set_self_loops("allow").unwrap();
+        db.add_edge(a, a).unwrap();
+        assert!(db.get_neighbors(a).unwrap().contains(&a));
+        // Walks never take the loop
+        let walk = db.random_walk(a, 20, 7, None);
+        assert!(walk.windows(2).all(|w| w[0] != w[1]), "{:?}", walk);
+
+        assert!(db.set_self_loops("keep").is_err());
+    }
+
+    #[test]
+    fn reembed_all_replaces_embeddings_and_search_uses_the_new_space() {
+        let mut db = fan_db(4);
+        add(&mut db, "target", vec![-1.0, 0.0]);
+        with_py(|py| {
+            let stub = py.eval("lambda text: [0.0, 0.0, 1.0] if text == 'target' else [1.0, float(len(text)), 0.0]", None, None)
+                .unwrap().into();
+            assert_eq!(db.reembed_all(py, stub).unwrap(), 5);
+        });
+        assert_eq!(db.dimension(), Some(3));
+        assert_eq!(db.embeddings.vector(4), vec![0.0, 0.0, 1.0]);
+        assert_eq!(db.embeddings.vector(0), vec![1.0, 6.0, 0.0]);
+        let results = db.hybrid_search(vec![0.0, 0.1, 1.0], 1, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(ids(&results), vec![4]);
+        assert!(db.hybrid_search(vec![1.0, 0.0], 1, None, None, None, None, None).is_err());
+
+        // One embedding of another dimension aborts without changing anything
+        with_py(|py| {
+            let ragged = py.eval("lambda text: [1.0] * (2 if text == 'target' else 3)", None, None).unwrap().into();
+            assert!(db.reembed_all(py, ragged).is_err());
+        });
+        assert_eq!(db.embeddings.vector(4), vec![0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn second_read_of_a_compressed_node_is_served_from_the_cache() {
+        let mut db = memory_db();
+        db.set_compression_threshold(Some(8));
+        let long = "a fairly long piece of content ".repeat(4);
+        let id = add(&mut db, &long, vec![1.0, 0.0]);
+        let (hits, misses) = db.content_cache_stats();
+
+        assert_eq!(db.get_node(id).unwrap(), long);
+        assert_eq!(db.content_cache_stats(), (hits, misses + 1));
+        assert_eq!(db.get_node(id).unwrap(), long);
+        assert_eq!(db.content_cache_stats(), (hits + 1, misses + 1));
+
+        // update_node invalidates the entry, so the new content is inflated
+        let replaced = "replacement content, also compressed ".repeat(4);
+        db.update_node(id, replaced.clone(), vec![1.0, 0.0]).unwrap();
+        let (hits, misses) = db.content_cache_stats();
+        assert_eq!(db.get_node(id).unwrap(), replaced);
+        assert_eq!(db.content_cache_stats(), (hits, misses + 1));
+
+        db.set_content_cache_capacity(0);
+        db.get_node(id).unwrap();
+        db.get_node(id).unwrap();
+        assert_eq!(db.content_cache_stats(), (hits, misses + 3));
+    }
+
+    #[test]
+    fn lru_and_lfu_evict_different_nodes() {
+        // 0: read three times, long ago. 1: read once, just now. 2: read twice, in between.
+        let build = || {
+            let mut db = memory_db();
+            db.enable_logical_clock(Some(1_000));
+            for i in 0..3 {
+                add(&mut db, &format!("node {}", i), vec![1.0, i as f32]);
+            }
+            for _ in 0..3 {
+                db.get_node(0).unwrap();
+            }
+            db.advance_clock(2_000).unwrap();
+            db.get_node(2).unwrap();
+            db.get_node(2).unwrap();
+            db.advance_clock(2_000).unwrap();
+            db.get_node(1).unwrap();
+            db
+        };
+
+        let mut lru = build();
+        lru.set_eviction_policy("lru").unwrap();
+        assert_eq!(lru.trim_to_capacity(2), vec![0]);
+
+        let mut lfu = build();
+        lfu.set_eviction_policy("lfu").unwrap();
+        assert_eq!(lfu.trim_to_capacity(2), vec![1]);
+        assert!(lfu.get_node(1).is_err());
+
+        assert!(build().set_eviction_policy("fifo").is_err());
+    }
+
+    #[test]
+    fn significance_on_a_custom_scale_maps_onto_0_255() {
+        let mut db = memory_db();
+        let top = db.add_node("top".to_string(), vec![1.0, 0.0], Some(9.0), Some(2.0), None, Some(9.0)).unwrap();
+        let half = db.add_node("half".to_string(), vec![0.0, 1.0], Some(4.5), Some(2.0), None, Some(9.0)).unwrap();
+        let raw = db.add_node("raw".to_string(), vec![1.0, 1.0], Some(200.0), Some(2.0), None, None).unwrap();
+        assert_eq!(db.headers[top as usize].significance, 255);
+        assert_eq!(db.headers[half as usize].significance, 128);
+        assert_eq!(db.headers[raw as usize].significance, 200);
+
+        assert!(db.add_node("over".to_string(), vec![1.0, 0.0], Some(9.5), Some(2.0), None, Some(9.0)).is_err());
+        assert!(db.add_node("over".to_string(), vec![1.0, 0.0], Some(256.0), Some(2.0), None, None).is_err());
+        assert_eq!(db.headers.len(), 3);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_content_and_embeddings() {
+        let path = temp_path("round-trip.db");
+        let mut db = memory_db();
+        db.set_compression_threshold(Some(16));
+        add(&mut db, "short", vec![1.0, 0.0]);
+        add(&mut db, &"long enough to be compressed ".repeat(3), vec![0.6, 0.8]);
+        add(&mut db, "ünïcode", vec![-0.5, 0.25]);
+        db.save(Some(path.clone())).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], SNAPSHOT_MAGIC);
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), SNAPSHOT_VERSION);
+
+        let loaded = SpiderDB::load(path.clone()).unwrap();
+        for id in 0..3 {
+            assert_eq!(loaded.get_node(id).unwrap(), db.get_node(id).unwrap());
+            assert_eq!(loaded.embeddings.vector(id as usize), db.embeddings.vector(id as usize));
+            assert_eq!(loaded.headers[id as usize].significance, db.headers[id as usize].significance);
+        }
+
+        // A file from a newer format is refused rather than misread
+        let mut future = bytes;
+        future[4..8].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, future).unwrap();
+        assert!(SpiderDB::load(path.clone()).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(SpiderDB::load(path).is_err());
+    }
+
+    #[test]
+    fn suggest_k_covers_every_connected_component() {
+        assert_eq!(memory_db().suggest_k(), 0);
+
+        // Two triangles with no edge between them
+        let mut db = fan_db(6);
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)] {
+            db.add_edge(a, b).unwrap();
+        }
+        assert!(db.suggest_k() >= 2, "{}", db.suggest_k());
+
+        // Joining them into one dense component lets the estimate drop
+        let mut joined = fan_db(6);
+        for a in 0..6 {
+            for b in a + 1..6 {
+                joined.add_edge(a, b).unwrap();
+            }
+        }
+        assert_eq!(joined.suggest_k(), 1);
+    }
+
+    #[test]
+    fn mapped_heap_reads_like_the_in_memory_one() {
+        let path = temp_path("mapped.db");
+        let mut db = memory_db();
+        db.set_compression_threshold(Some(16));
+        add(&mut db, "plain", vec![1.0, 0.0]);
+        add(&mut db, &"compressed content ".repeat(4), vec![0.0, 1.0]);
+        add(&mut db, "ünïcode", vec![1.0, 1.0]);
+        db.save(Some(path.clone())).unwrap();
+
+        let mut mapped = SpiderDB::open_mmap(path.clone(), None).unwrap();
+        let mut loaded = SpiderDB::load(path.clone()).unwrap();
+        assert!(mapped.data_heap.is_mapped() && !loaded.data_heap.is_mapped());
+        for id in 0..3 {
+            assert_eq!(mapped.get_node(id).unwrap(), loaded.get_node(id).unwrap());
+        }
+        assert!(mapped.data_heap.is_mapped());
+
+        // A header reaching past the end of the heap fails the same way
+        for db in [&mut mapped, &mut loaded] {
+            let heap_len = db.data_heap.len() as u64;
+            db.headers[0].data_offset = heap_len - 2;
+            db.headers[0].data_len = 8;
+            assert!(db.content(0).is_none());
+            assert!(db.get_node(0).is_err());
+        }
+
+        // The first write copies the heap into memory
+        let id = add(&mut mapped, "new", vec![0.5, 0.5]);
+        assert!(!mapped.data_heap.is_mapped());
+        assert_eq!(mapped.get_node(id).unwrap(), "new");
+        assert_eq!(mapped.get_node(2).unwrap(), "ünïcode");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn vacuum_compact_removes_a_middle_node_and_renumbers_the_rest() {
+        let mut db = memory_db();
+        let contents = ["zero", "one", "doomed", "three", "four"];
+        for (i, content) in contents.iter().enumerate() {
+            let significance = if i == 2 { 0.0 } else { 200.0 };
+            db.add_node(content.to_string(), vec![1.0, i as f32], Some(significance), Some(2.0), None, None).unwrap();
+            if i > 0 {
+                db.add_edge(i as u64 - 1, i as u64).unwrap();
+            }
+        }
+        let heap_before = db.data_heap.len();
+
+        assert_eq!(db.vacuum_compact(1e-6), vec![2]);
+        for (id, content) in ["zero", "one", "three", "four"].iter().enumerate() {
+            assert_eq!(db.get_node(id as u64).unwrap(), *content);
+        }
+        assert_eq!(db.embeddings.vector(2), vec![1.0, 3.0]);
+        assert_eq!(db.data_heap.len(), heap_before - "doomed".len());
+        assert!(db.headers.windows(2).all(|w| w[0].data_offset + w[0].data_len as u64 == w[1].data_offset));
+
+        // The edges through the removed node are gone; the others follow the renumbering
+        assert_eq!(db.get_neighbors(1).unwrap(), vec![0]);
+        assert_eq!(db.get_neighbors(2).unwrap(), vec![3]);
+        assert_eq!(db.validate(), Ok(()));
+    }
+
+    #[test]
+    fn subspace_search_ignores_the_other_dimensions() {
+        let mut db = memory_db();
+        // [title | body]
+        let a = add(&mut db, "a", vec![1.0, 0.0, 0.0, 1.0]);
+        let b = add(&mut db, "b", vec![0.0, 1.0, 1.0, 0.0]);
+        db.register_subspace("title".to_string(), 0, 2).unwrap();
+        db.register_subspace("body".to_string(), 2, 4).unwrap();
+
+        let title = db.search_subspace("title", vec![1.0, 0.0], 2).unwrap();
+        assert_eq!(ids(&title), vec![a, b]);
+        assert!((title[0].1 - 1.0).abs() < 1e-6);
+        let body = db.search_subspace("body", vec![1.0, 0.0], 2).unwrap();
+        assert_eq!(ids(&body), vec![b, a]);
+
+        assert!(db.search_subspace("summary", vec![1.0, 0.0], 2).is_err());
+        assert!(db.search_subspace("title", vec![1.0, 0.0, 0.0], 2).is_err());
+        assert!(db.register_subspace("empty".to_string(), 2, 2).is_err());
+        assert!(db.register_subspace("past".to_string(), 2, 5).is_err());
+    }
+
+    #[test]
+    fn update_node_replaces_content_in_place() {
+        let mut db = path_db(3);
+        let longer = "node 1, rewritten with much longer content than before".to_string();
+        assert!(db.update_node(1, longer.clone(), vec![-1.0, 0.0]).unwrap());
+
+        assert_eq!(db.get_node(1).unwrap(), longer);
+        assert_eq!(db.get_node(0).unwrap(), "node 0");
+        assert_eq!(db.get_node(2).unwrap(), "node 2");
+        let mut neighbors = db.get_neighbors(1).unwrap();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![0, 2]);
+        assert_eq!(db.find_by_content(longer.as_bytes()), Some(1));
+        assert_eq!(db.find_by_content(b"node 1"), None);
+        let nearest = db.hybrid_search(vec![-1.0, 0.0], 1, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(ids(&nearest), vec![1]);
+
+        assert!(!db.update_node(3, "missing".to_string(), vec![1.0, 0.0]).unwrap());
+        assert!(db.update_node(1, "wrong dimension".to_string(), vec![1.0, 0.0, 0.0]).is_err());
+        assert_eq!(db.get_node(1).unwrap(), longer);
+    }
+
+    #[test]
+    fn repeated_updates_extend_the_index_instead_of_rebuilding_it() {
+        let mut db = fan_db(24);
+        let update = |db: &mut SpiderDB, step: usize| {
+            let angle = 3.0 + step as f32 * 0.01;
+            db.update_node(0, "node 0".to_string(), vec![angle.cos(), angle.sin()]).unwrap();
+        };
+        for step in 1..=20 {
+            update(&mut db, step);
+        }
+        assert_eq!(db.index.len(), 24);
+        assert_eq!(db.index.stale(), 20);
+
+        // Hits on node 0's old vectors are re-scored against its current one
+        for angle in [0.0f32, 0.7, 3.2] {
+            let query = [angle.cos(), angle.sin()];
+            let hits = db.nearest(&query, 3, None);
+            let exact = search::find_similar_vectors(&query, &db.embeddings.to_vecs(), 3);
+            assert_eq!(ids(&hits), exact.iter().map(|&(i, _)| i as u64).collect::<Vec<_>>(), "{}", angle);
+            for ((_, sim), (_, expected)) in hits.iter().zip(&exact) {
+                assert!((sim - expected).abs() < 1e-5);
+            }
+        }
+
+        // One rebuild once the old vectors outnumber the nodes
+        for step in 21..=25 {
+            update(&mut db, step);
+        }
+        assert_eq!(db.index.stale(), 0);
+        assert_eq!(db.index.len(), 24);
+        assert_eq!(ids(&db.nearest(&[3.25f32.cos(), 3.25f32.sin()], 1, None)), vec![0]);
+    }
+
+    #[test]
+    fn zero_query_raises_or_returns_nothing_per_mode() {
+        let mut db = fan_db(4);
+        with_py(|py| {
+            for query in [vec![0.0, 0.0], vec![f32::NAN, 1.0], vec![1.0, 0.0, 0.0]] {
+                let err = db.hybrid_search(query.clone(), 2, None, None, None, None, None).unwrap_err();
+                assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py), "{:?}", query);
+                assert!(db.run(&SearchQuery::new(query, 2)).is_err());
+            }
+        });
+
+        db.set_invalid_query_mode("empty").unwrap();
+        assert!(db.hybrid_search(vec![0.0, 0.0], 2, None, None, None, None, None).unwrap().into_scores().is_empty());
+        assert!(db.set_invalid_query_mode("ignore").is_err());
+        assert_eq!(db.hybrid_search(vec![1.0, 0.0], 2, None, None, None, None, None).unwrap().into_scores().len(), 2);
+    }
+
+    #[test]
+    fn mapped_embeddings_are_searched_without_an_index() {
+        let path = temp_path("mapped-search.db");
+        let resaved = temp_path("mapped-resave.db");
+        let mut db = fan_db(12);
+        for (a, b) in [(0, 1), (1, 2), (5, 9)] {
+            db.add_edge(a, b).unwrap();
+        }
+        db.enable_logical_clock(Some(1_000));
+        db.save(Some(path.clone())).unwrap();
+
+        let mut mapped = SpiderDB::open_mmap(path.clone(), Some(true)).unwrap();
+        let mut loaded = SpiderDB::load(path.clone()).unwrap();
+        for db in [&mut mapped, &mut loaded] {
+            db.enable_logical_clock(Some(1_000));
+        }
+        assert!(mapped.embeddings.is_mapped() && mapped.index.is_empty());
+        assert_eq!(mapped.validate(), Ok(()));
+        for id in 0..12 {
+            assert_eq!(mapped.embeddings.vector(id), loaded.embeddings.vector(id));
+        }
+        for query in [vec![1.0, 0.0], vec![0.3, 0.9], vec![-1.0, 0.2]] {
+            let from_map = mapped.hybrid_search(query.clone(), 4, None, None, None, None, None).unwrap().into_scores();
+            assert_eq!(from_map, loaded.hybrid_search(query.clone(), 4, None, None, None, None, None).unwrap().into_scores());
+            with_py(|py| {
+                assert_eq!(mapped.exact_search(py, query.clone(), 4).unwrap(), loaded.exact_search(py, query, 4).unwrap());
+            });
+        }
+        assert_eq!(mapped.get_edge_weight(5, 9), loaded.get_edge_weight(5, 9));
+
+        // Saving over the mapped file keeps the vectors it reads from intact
+        mapped.save(Some(path.clone())).unwrap();
+        mapped.save(Some(resaved.clone())).unwrap();
+        let reloaded = SpiderDB::load(resaved.clone()).unwrap();
+        assert_eq!(reloaded.embeddings.to_vecs(), loaded.embeddings.to_vecs());
+        assert_eq!(mapped.embeddings.to_vecs(), loaded.embeddings.to_vecs());
+
+        // The first write copies the vectors into memory and indexes them
+        add(&mut mapped, "node 12", vec![1.0, 0.0]);
+        assert!(!mapped.embeddings.is_mapped());
+        assert_eq!(mapped.index.len(), 13);
+        assert_eq!(mapped.validate(), Ok(()));
+        for path in [path, resaved] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn search_components_agree_with_the_combined_score() {
+        // Nodes from two days ago and fresh ones, interleaved by angle, plus a
+        // fresh twin of the oldest node
+        let build = || {
+            let mut db = memory_db();
+            db.enable_logical_clock(Some(0));
+            for i in 0..4 {
+                let angle = i as f32 * 0.3;
+                add(&mut db, &format!("old {}", i), vec![angle.cos(), angle.sin()]);
+            }
+            db.advance_clock(48 * 3600).unwrap();
+            for i in 0..4 {
+                let angle = i as f32 * 0.3 + 0.15;
+                add(&mut db, &format!("fresh {}", i), vec![angle.cos(), angle.sin()]);
+            }
+            add(&mut db, "fresh twin", vec![1.0, 0.0]);
+            db
+        };
+        let query = vec![1.0, 0.0];
+        let components = |normalize| match build().hybrid_search(query.clone(), 9, None, normalize, None, None, Some(true)).unwrap() {
+            SearchResults::Components(parts) => parts,
+            SearchResults::Scores(_) => panic!("asked for components"),
+        };
+
+        let parts = components(None);
+        let plain = build().hybrid_search(query.clone(), 9, None, None, None, None, None).unwrap().into_scores();
+        assert_eq!(plain.len(), 9);
+        assert_eq!(parts.iter().map(|&(id, _, _, combined)| (id, combined)).collect::<Vec<_>>(), plain);
+
+        // The components are the similarity and life score at ranking time
+        let db = build();
+        for &(id, similarity, life_score, _) in &parts {
+            assert!((similarity - search::cosine_similarity(&query, &db.embeddings.vector(id as usize))).abs() < 1e-5);
+            assert_eq!(life_score, db.calculate_life_score(id).unwrap());
+        }
+
+        // Beating another node on both factors (so on sim x life) always ranks higher
+        let mut dominating_pairs = 0;
+        for a in &parts {
+            for b in parts.iter().filter(|b| a.1 > b.1 && a.2 > b.2) {
+                assert!(a.3 > b.3, "{:?} vs {:?}", a, b);
+                dominating_pairs += 1;
+            }
+        }
+        assert!(dominating_pairs > 0);
+        // The twin matches its old counterpart's similarity but outlives it
+        assert_eq!(parts[0].0, 8);
+
+        // normalize rescales combined only
+        let normalized = components(Some(true));
+        assert_eq!(normalized[0].3, 1.0);
+        assert!(normalized.iter().all(|&(_, _, _, combined)| (0.0..=1.0).contains(&combined)));
+        let factors = |parts: &[(u64, f32, f32, f32)]| parts.iter().map(|&(id, s, l, _)| (id, s, l)).collect::<Vec<_>>();
+        assert_eq!(factors(&normalized), factors(&parts));
+    }
+
+    #[test]
+    fn product_combiner_scores_similarity_times_life_score() {
+        let mut db = memory_db();
+        db.enable_logical_clock(Some(0));
+        for i in 0..4 {
+            let angle = i as f32 * 0.3;
+            add(&mut db, &format!("old {}", i), vec![angle.cos(), angle.sin()]);
+        }
+        db.advance_clock(48 * 3600).unwrap();
+        for i in 0..4 {
+            let angle = i as f32 * 0.3 + 0.15;
+            add(&mut db, &format!("fresh {}", i), vec![angle.cos(), angle.sin()]);
+        }
+        assert!(db.set_score_combiner("average").is_err());
+        db.set_score_combiner("Product").unwrap();
+
+        let components = |db: &SpiderDB| match db.hybrid_search(vec![1.0, 0.0], 8, None, None, None, None, Some(true)).unwrap() {
+            SearchResults::Components(parts) => parts,
+            SearchResults::Scores(_) => panic!("asked for components"),
+        };
+        let parts = components(&db);
+        assert_eq!(parts.len(), 8);
+        for &(_, similarity, life_score, combined) in &parts {
+            assert_eq!(combined, similarity * life_score);
+        }
+        assert!(parts.windows(2).all(|pair| pair[0].3 >= pair[1].3));
+        // Old node 0 is the closest match, but the fresh ones outlive it
+        assert!(parts[0].0 >= 4);
+
+        // The life score in the product is floored like the bio term's
+        db.set_bio_config(None, None, None, None, Some(1e6), None).unwrap();
+        for (_, similarity, life_score, combined) in components(&db) {
+            assert_eq!(life_score, 1e6);
+            assert_eq!(combined, similarity * 1e6);
+        }
+    }
+
+    #[test]
+    fn first_embedding_sets_the_dimension() {
+        let mut db = memory_db();
+        assert_eq!(db.dimension(), None);
+        add(&mut db, "first", vec![1.0, 0.0, 0.0]);
+        add(&mut db, "second", vec![0.0, 1.0, 0.0]);
+        assert_eq!(db.dimension(), Some(3));
+        assert_eq!(db.add_node_f64("third".to_string(), vec![0.0, 0.0, 1.0], None, Some(2.0), None, None).unwrap(), 2);
+    }
+
+    #[test]
+    fn embedding_of_another_dimension_is_rejected() {
+        let mut db = memory_db();
+        add(&mut db, "first", vec![1.0, 0.0, 0.0]);
+        with_py(|py| {
+            let err = db.add_node("short".to_string(), vec![1.0, 0.0], None, Some(2.0), None, None).unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        });
+        assert!(db.add_node_f64("long".to_string(), vec![1.0; 4], None, Some(2.0), None, None).is_err());
+        assert_eq!(db.headers.len(), 1);
+        assert_eq!(db.dimension(), Some(3));
+    }
+
+    #[test]
+    fn empty_embedding_is_rejected() {
+        let mut db = memory_db();
+        assert!(db.add_node("empty".to_string(), Vec::new(), None, Some(2.0), None, None).is_err());
+        assert_eq!(db.dimension(), None);
+        add(&mut db, "first", vec![1.0, 0.0]);
+        assert!(db.add_node("empty".to_string(), Vec::new(), None, Some(2.0), None, None).is_err());
+        assert_eq!(db.headers.len(), 1);
     }
 }
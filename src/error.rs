@@ -0,0 +1,115 @@
+use pyo3::exceptions::{PyIOError, PyKeyError, PyRuntimeError, PyValueError};
+use pyo3::PyErr;
+use std::fmt;
+
+/// Errors raised by SpiderDB operations.
+///
+/// Each variant maps to a distinct Python exception type so callers can tell
+/// failure modes apart instead of receiving a bare None.
+#[derive(Debug)]
+pub enum SpiderError {
+    /// No node with this id exists (raised as KeyError)
+    NodeNotFound(u64),
+    /// No cluster with this id in the current hierarchy (raised as KeyError)
+    ClusterNotFound(u64),
+    /// An embedding's length differs from the database's (raised as ValueError)
+    DimensionMismatch { expected: usize, got: usize },
+    /// An embedding has no components (raised as ValueError)
+    EmptyEmbedding,
+    /// A query embedding is all zeros or not finite (raised as ValueError)
+    ZeroQuery,
+    /// The node's content is not valid UTF-8 (raised as ValueError)
+    InvalidUtf8(u64),
+    /// The node's header points past the data heap, or its compressed
+    /// content cannot be inflated (raised as RuntimeError)
+    Truncated(u64),
+    /// A configuration value is out of its valid range (raised as ValueError)
+    InvalidConfig(String),
+    /// A weighted traversal met an edge with negative cost (raised as ValueError)
+    NegativeCost { source: u64, target: u64, cost: f32 },
+    /// Filesystem failure while reading or writing (raised as IOError)
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SpiderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiderError::NodeNotFound(id) => write!(f, "Node {} not found", id),
+            SpiderError::ClusterNotFound(id) => write!(f, "Cluster {} not found", id),
+            SpiderError::DimensionMismatch { expected, got } => {
+                write!(f, "Embedding dimension mismatch: expected {}, got {}", expected, got)
+            }
+            SpiderError::EmptyEmbedding => write!(f, "Embedding is empty"),
+            SpiderError::ZeroQuery => write!(f, "Query embedding has zero or non-finite norm"),
+            SpiderError::InvalidUtf8(id) => write!(f, "Node {} content is not valid UTF-8", id),
+            SpiderError::Truncated(id) => write!(f, "Node {} content is truncated or corrupt", id),
+            SpiderError::InvalidConfig(reason) => write!(f, "Invalid configuration: {}", reason),
+            SpiderError::NegativeCost { source, target, cost } => {
+                write!(f, "Edge {} -> {} has negative cost {}", source, target, cost)
+            }
+            SpiderError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SpiderError {}
+
+impl From<std::io::Error> for SpiderError {
+    fn from(e: std::io::Error) -> Self {
+        SpiderError::Io(e)
+    }
+}
+
+impl From<SpiderError> for PyErr {
+    fn from(e: SpiderError) -> Self {
+        let message = e.to_string();
+        match e {
+            SpiderError::NodeNotFound(_) | SpiderError::ClusterNotFound(_) => {
+                PyKeyError::new_err(message)
+            }
+            SpiderError::DimensionMismatch { .. }
+            | SpiderError::EmptyEmbedding
+            | SpiderError::ZeroQuery
+            | SpiderError::InvalidUtf8(_)
+            | SpiderError::InvalidConfig(_)
+            | SpiderError::NegativeCost { .. } => {
+                PyValueError::new_err(message)
+            }
+            SpiderError::Truncated(_) => PyRuntimeError::new_err(message),
+            SpiderError::Io(_) => PyIOError::new_err(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::{PyTypeInfo, Python};
+
+    /// Asserts `error` is raised in Python as exception type `T`.
+    fn raises<T: PyTypeInfo>(py: Python<'_>, error: SpiderError) {
+        let message = error.to_string();
+        let err = PyErr::from(error);
+        assert!(err.is_instance_of::<T>(py), "{} raised as {}", message, err.get_type(py));
+        // KeyError quotes its message
+        assert!(err.value(py).to_string().contains(&message));
+    }
+
+    #[test]
+    fn each_variant_maps_to_its_exception() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            raises::<PyKeyError>(py, SpiderError::NodeNotFound(7));
+            raises::<PyKeyError>(py, SpiderError::ClusterNotFound(7));
+            raises::<PyValueError>(py, SpiderError::DimensionMismatch { expected: 3, got: 2 });
+            raises::<PyValueError>(py, SpiderError::EmptyEmbedding);
+            raises::<PyValueError>(py, SpiderError::ZeroQuery);
+            raises::<PyValueError>(py, SpiderError::InvalidUtf8(7));
+            raises::<PyValueError>(py, SpiderError::InvalidConfig("bad".to_string()));
+            raises::<PyValueError>(py, SpiderError::NegativeCost { source: 1, target: 2, cost: -0.5 });
+            raises::<PyRuntimeError>(py, SpiderError::Truncated(7));
+            raises::<PyIOError>(py, SpiderError::Io(std::io::Error::other("disk")));
+            assert!(!PyErr::from(SpiderError::NodeNotFound(7)).is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,435 @@
+use crate::error::SpiderError;
+use crate::search;
+use crate::storage::Embeddings;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+
+/// What `add_edge` does with an edge from a node to itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfLoops {
+    /// Store it like any other edge (scoring and walks still ignore it)
+    Allow,
+    /// Silently skip it
+    Drop,
+    /// Refuse it with an error
+    Reject,
+}
+
+impl SelfLoops {
+    /// Parses "allow", "drop" or "reject" (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "allow" => Some(SelfLoops::Allow),
+            "drop" => Some(SelfLoops::Drop),
+            "reject" => Some(SelfLoops::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// Random walk over the adjacency list starting at `start`.
+///
+/// Each step moves to a neighbor (self-loops excluded) chosen with probability
+/// proportional to `weight(current, neighbor)`; negative weights count as zero,
+/// and if every neighbor weighs zero the choice is uniform. With probability
+/// `restart_prob` it jumps back to `start` instead. The walk ends early at a
+/// node with no neighbors. The same `seed` always yields the same walk.
+///
+/// Returns the visited sequence, beginning with `start`.
+pub fn random_walk<W: Fn(u64, u64) -> f32>(
+    edge_list: &[Vec<u64>],
+    start: u64,
+    steps: usize,
+    seed: u64,
+    restart_prob: f32,
+    weight: W,
+) -> Vec<u64> {
+    if start as usize >= edge_list.len() {
+        return Vec::new();
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut walk = Vec::with_capacity(steps + 1);
+    let mut current = start;
+    walk.push(current);
+
+    for _ in 0..steps {
+        if restart_prob > 0.0 && rng.gen::<f32>() < restart_prob {
+            current = start;
+            walk.push(current);
+            continue;
+        }
+
+        let neighbors: Vec<u64> = edge_list[current as usize].iter()
+            .copied()
+            .filter(|&n| n != current)
+            .collect();
+        if neighbors.is_empty() {
+            break;
+        }
+        let weights: Vec<f32> = neighbors.iter().map(|&n| weight(current, n).max(0.0)).collect();
+        current = match WeightedIndex::new(&weights) {
+            Ok(choice) => neighbors[choice.sample(&mut rng)],
+            // All weights zero
+            Err(_) => neighbors[rng.gen_range(0..neighbors.len())],
+        };
+        walk.push(current);
+    }
+
+    walk
+}
+
+/// Nodes exactly `hop` edges away from `start` by shortest path (the BFS
+/// layer at that depth), in discovery order. Closer nodes are excluded;
+/// `hop == 0` yields just `start`.
+pub fn neighbors_at_hop(edge_list: &[Vec<u64>], start: u64, hop: usize) -> Vec<u64> {
+    if start as usize >= edge_list.len() {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::from([start]);
+    let mut frontier = vec![start];
+    for _ in 0..hop {
+        let mut next = Vec::new();
+        for &node in &frontier {
+            for &n in &edge_list[node as usize] {
+                if seen.insert(n) {
+                    next.push(n);
+                }
+            }
+        }
+        if next.is_empty() {
+            return next;
+        }
+        frontier = next;
+    }
+    frontier
+}
+
+/// Strength of the edge `source -> target`: the cosine similarity of its
+/// endpoints' embeddings. Returns None if there is no such edge.
+pub fn edge_weight(
+    edge_list: &[Vec<u64>],
+    embeddings: &Embeddings,
+    source: u64,
+    target: u64,
+) -> Option<f32> {
+    if !edge_list.get(source as usize)?.contains(&target) {
+        return None;
+    }
+    let a = embeddings.get(source as usize)?;
+    let b = embeddings.get(target as usize)?;
+    Some(search::cosine_similarity(&a, &b))
+}
+
+/// Weight multiplier for an edge created at `created_ts`, evaluated at `now`
+/// (Unix seconds): halves every `half_life_hours`. 1.0 when decay is off (None).
+pub fn age_decay(created_ts: u64, now: u64, half_life_hours: Option<f32>) -> f32 {
+    match half_life_hours {
+        Some(half_life) if half_life > 0.0 => {
+            let hours = now.saturating_sub(created_ts) as f32 / 3600.0;
+            0.5f32.powf(hours / half_life)
+        }
+        _ => 1.0,
+    }
+}
+
+/// Frontier entry for Dijkstra, ordered so the cheapest pops first.
+struct Frontier(f32, u64);
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then_with(|| other.1.cmp(&self.1))
+    }
+}
+
+/// Dijkstra shortest path from `from` to `to`.
+///
+/// `cost(a, b)` gives the cost of traversing the edge a -> b. Dijkstra is
+/// only correct for nonnegative costs, so the first negative cost it meets
+/// is an error. Returns the path (both endpoints included) and its total
+/// cost, or None if `to` is unreachable.
+pub fn shortest_path_weighted<F: Fn(u64, u64) -> f32>(
+    edge_list: &[Vec<u64>],
+    from: u64,
+    to: u64,
+    cost: F,
+) -> Result<Option<(Vec<u64>, f32)>, SpiderError> {
+    let n = edge_list.len();
+    if from as usize >= n || to as usize >= n {
+        return Ok(None);
+    }
+
+    let mut dist = vec![f32::INFINITY; n];
+    let mut prev: Vec<Option<u64>> = vec![None; n];
+    let mut heap = BinaryHeap::new();
+    dist[from as usize] = 0.0;
+    heap.push(Frontier(0.0, from));
+
+    while let Some(Frontier(d, node)) = heap.pop() {
+        if node == to {
+            break;
+        }
+        if d > dist[node as usize] {
+            continue; // Stale entry
+        }
+
+        for &next in &edge_list[node as usize] {
+            let edge_cost = cost(node, next);
+            if edge_cost < 0.0 {
+                return Err(SpiderError::NegativeCost { source: node, target: next, cost: edge_cost });
+            }
+            let candidate = d + edge_cost;
+            if candidate < dist[next as usize] {
+                dist[next as usize] = candidate;
+                prev[next as usize] = Some(node);
+                heap.push(Frontier(candidate, next));
+            }
+        }
+    }
+
+    if dist[to as usize].is_infinite() {
+        return Ok(None);
+    }
+
+    let mut path = vec![to];
+    let mut current = to;
+    while let Some(p) = prev[current as usize] {
+        path.push(p);
+        current = p;
+    }
+    path.reverse();
+
+    Ok(Some((path, dist[to as usize])))
+}
+
+/// Connected components of `edge_list` taken as undirected, each sorted by
+/// id. Nodes without edges come back as single-node components.
+pub fn connected_components(edge_list: &[Vec<u64>]) -> Vec<Vec<u64>> {
+    let n = edge_list.len();
+    let mut undirected = vec![Vec::new(); n];
+    for (source, targets) in edge_list.iter().enumerate() {
+        for &target in targets {
+            let target = target as usize;
+            if target < n {
+                undirected[source].push(target);
+                undirected[target].push(source);
+            }
+        }
+    }
+
+    let mut seen = vec![false; n];
+    let mut components = Vec::new();
+    for start in 0..n {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut component = vec![start as u64];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &next in &undirected[node] {
+                if !seen[next] {
+                    seen[next] = true;
+                    component.push(next as u64);
+                    stack.push(next);
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
+
+/// Nodes whose removal would split their connected component (cut vertices),
+/// treating `edge_list` as an undirected simple graph. Sorted by id.
+pub fn articulation_points(edge_list: &[Vec<u64>]) -> Vec<u64> {
+    cut_structure(edge_list).0
+}
+
+/// Edges whose removal would split their connected component, as (lower id,
+/// higher id) over the undirected simple view of `edge_list`. Sorted.
+pub fn bridges(edge_list: &[Vec<u64>]) -> Vec<(u64, u64)> {
+    cut_structure(edge_list).1
+}
+
+/// Tarjan's low-link DFS, run iteratively so long chains can't overflow the
+/// stack. Duplicate and reverse entries collapse into one undirected edge and
+/// self-loops are ignored. Returns (articulation points, bridges).
+fn cut_structure(edge_list: &[Vec<u64>]) -> (Vec<u64>, Vec<(u64, u64)>) {
+    let n = edge_list.len();
+    let mut adjacency = vec![BTreeSet::new(); n];
+    for (source, targets) in edge_list.iter().enumerate() {
+        for &target in targets {
+            let target = target as usize;
+            if target != source && target < n {
+                adjacency[source].insert(target);
+                adjacency[target].insert(source);
+            }
+        }
+    }
+    let adjacency: Vec<Vec<usize>> = adjacency.into_iter().map(|set| set.into_iter().collect()).collect();
+
+    const UNVISITED: usize = usize::MAX;
+    let mut discovered = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut is_cut = vec![false; n];
+    let mut bridges = Vec::new();
+    let mut timer = 0;
+
+    for root in 0..n {
+        if discovered[root] != UNVISITED || adjacency[root].is_empty() {
+            continue;
+        }
+        discovered[root] = timer;
+        low[root] = timer;
+        timer += 1;
+        let mut root_children = 0;
+        // (node, parent, index of the next neighbor to visit)
+        let mut stack = vec![(root, UNVISITED, 0)];
+
+        while let Some(&(node, parent, next)) = stack.last() {
+            if let Some(&neighbor) = adjacency[node].get(next) {
+                stack.last_mut().unwrap().2 += 1;
+                if neighbor == parent {
+                    continue;
+                }
+                if discovered[neighbor] == UNVISITED {
+                    discovered[neighbor] = timer;
+                    low[neighbor] = timer;
+                    timer += 1;
+                    if node == root {
+                        root_children += 1;
+                    }
+                    stack.push((neighbor, node, 0));
+                } else {
+                    low[node] = low[node].min(discovered[neighbor]);
+                }
+            } else {
+                stack.pop();
+                if parent == UNVISITED {
+                    continue;
+                }
+                low[parent] = low[parent].min(low[node]);
+                if low[node] > discovered[parent] {
+                    bridges.push((parent.min(node) as u64, parent.max(node) as u64));
+                }
+                if parent != root && low[node] >= discovered[parent] {
+                    is_cut[parent] = true;
+                }
+            }
+        }
+        if root_children > 1 {
+            is_cut[root] = true;
+        }
+    }
+
+    let points = (0..n).filter(|&i| is_cut[i]).map(|i| i as u64).collect();
+    bridges.sort_unstable();
+    (points, bridges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Undirected adjacency list from an edge list.
+    fn adjacency(n: usize, edges: &[(u64, u64)]) -> Vec<Vec<u64>> {
+        let mut edge_list = vec![Vec::new(); n];
+        for &(a, b) in edges {
+            edge_list[a as usize].push(b);
+            edge_list[b as usize].push(a);
+        }
+        edge_list
+    }
+
+    /// How often a walk from the hub of a star steps to each leaf.
+    fn leaf_visits(weight: impl Fn(u64, u64) -> f32) -> [usize; 3] {
+        let star = adjacency(3, &[(0, 1), (0, 2)]);
+        let mut visits = [0; 3];
+        for &node in &random_walk(&star, 0, 2000, 3, 0.0, weight) {
+            visits[node as usize] += 1;
+        }
+        visits
+    }
+
+    #[test]
+    fn random_walk_is_reproducible_and_follows_edges() {
+        let edge_list = adjacency(5, &[(0, 1), (1, 2), (2, 3), (3, 0), (2, 4)]);
+        let walk = random_walk(&edge_list, 0, 50, 42, 0.0, |_, _| 1.0);
+
+        assert_eq!(walk.len(), 51);
+        assert_eq!(walk, random_walk(&edge_list, 0, 50, 42, 0.0, |_, _| 1.0));
+        assert!(walk.windows(2).all(|w| edge_list[w[0] as usize].contains(&w[1])));
+    }
+
+    #[test]
+    fn random_walk_steps_in_proportion_to_weight() {
+        let visits = leaf_visits(|_, b| if b == 1 { 0.9 } else { 0.1 });
+        assert!(visits[1] > 7 * visits[2], "{:?}", visits);
+
+        // Negative weights count as zero
+        let visits = leaf_visits(|_, b| if b == 1 { 1.0 } else { -1.0 });
+        assert_eq!(visits[2], 0);
+
+        // All-zero weights fall back to uniform
+        let visits = leaf_visits(|_, _| 0.0);
+        assert!(visits[1] > 400 && visits[2] > 400, "{:?}", visits);
+    }
+
+    #[test]
+    fn shortest_path_prefers_lower_cost_over_fewer_hops() {
+        // Direct edge 0-3 costs 10; the detour 0-1-2-3 costs 3
+        let edge_list = adjacency(4, &[(0, 3), (0, 1), (1, 2), (2, 3)]);
+        let cost = |a: u64, b: u64| if a.min(b) == 0 && a.max(b) == 3 { 10.0 } else { 1.0 };
+
+        let (path, total) = shortest_path_weighted(&edge_list, 0, 3, cost).unwrap().unwrap();
+        assert_eq!(path, [0, 1, 2, 3]);
+        assert_eq!(total, 3.0);
+    }
+
+    #[test]
+    fn shortest_path_rejects_negative_costs() {
+        let edge_list = adjacency(3, &[(0, 1), (1, 2)]);
+        let result = shortest_path_weighted(&edge_list, 0, 2, |a, _| if a == 1 { -0.5 } else { 1.0 });
+        assert!(matches!(result, Err(SpiderError::NegativeCost { source: 1, .. })));
+    }
+
+    #[test]
+    fn neighbors_at_hop_returns_only_that_layer() {
+        let path = adjacency(6, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)]);
+        assert_eq!(neighbors_at_hop(&path, 0, 3), [3]);
+        assert_eq!(neighbors_at_hop(&path, 2, 2), [0, 4]);
+        assert_eq!(neighbors_at_hop(&path, 0, 0), [0]);
+        assert!(neighbors_at_hop(&path, 0, 9).is_empty());
+    }
+
+    #[test]
+    fn path_inner_nodes_are_cut_points_and_cycles_have_none() {
+        let path = adjacency(4, &[(0, 1), (1, 2), (2, 3), (1, 2)]);
+        assert_eq!(articulation_points(&path), vec![1, 2]);
+        assert_eq!(bridges(&path), vec![(0, 1), (1, 2), (2, 3)]);
+
+        let cycle = adjacency(4, &[(0, 1), (1, 2), (2, 3), (3, 0), (2, 2)]);
+        assert!(articulation_points(&cycle).is_empty());
+        assert!(bridges(&cycle).is_empty());
+    }
+}
@@ -1,17 +1,38 @@
+// pyo3 0.20's #[pymethods] expansion trips this lint on newer toolchains.
+#![allow(non_local_definitions)]
+
 use pyo3::prelude::*;
 
 pub mod bio;
+pub mod bloom;
 pub mod db;
+pub mod error;
+pub mod graph;
 pub mod search;
 pub mod storage;
 pub mod cluster;
+pub mod query;
 pub mod ranking;
 
-use db::SpiderDB;
+use cluster::ClusterStats;
+use db::{DbDiff, NodeRecord, SpiderDB};
+use query::SearchQuery;
+
+/// Converts a significance on a 0..=`scale_max` scale (e.g. 0-9) to the
+/// 0-255 scale nodes store. Raises ValueError for values off the scale.
+#[pyfunction]
+fn significance_from_scale(value: f32, scale_max: f32) -> PyResult<u8> {
+    Ok(storage::significance_from_scale(value, scale_max)?)
+}
 
 /// A Python module implemented in Rust.
 #[pymodule]
 fn spider(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<SpiderDB>()?;
+    m.add_class::<ClusterStats>()?;
+    m.add_class::<NodeRecord>()?;
+    m.add_class::<DbDiff>()?;
+    m.add_class::<SearchQuery>()?;
+    m.add_function(wrap_pyfunction!(significance_from_scale, m)?)?;
     Ok(())
 }
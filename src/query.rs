@@ -0,0 +1,157 @@
+use pyo3::prelude::*;
+use std::collections::HashSet;
+
+/// A hybrid search request built up fluently and executed with `SpiderDB.run`.
+///
+/// Every setter returns the query itself, so options chain:
+/// `SearchQuery(emb, 10).exclude([3, 4]).normalize(True)`. Options left unset
+/// behave exactly as the matching `hybrid_search*` defaults.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub(crate) embedding: Vec<f32>,
+    pub(crate) k: usize,
+    pub(crate) ef_search: Option<usize>,
+    pub(crate) normalize: bool,
+    pub(crate) softmax_temperature: Option<f32>,
+    pub(crate) dedup_by_content: bool,
+    /// Return (id, similarity, life_score, combined) instead of (id, score)
+    pub(crate) with_components: bool,
+    /// Ids that must not appear in the results
+    pub(crate) exclude: HashSet<u64>,
+    /// Inclusive (min, max) significance range
+    pub(crate) significance_band: Option<(u8, u8)>,
+    /// Per-query overrides of the database's semantic/graph/bio/cluster weights
+    pub(crate) semantic_weight: Option<f32>,
+    pub(crate) graph_weight: Option<f32>,
+    pub(crate) bio_weight: Option<f32>,
+    pub(crate) cluster_weight: Option<f32>,
+}
+
+#[pymethods]
+impl SearchQuery {
+    #[new]
+    pub fn new(query_embedding: Vec<f32>, k: usize) -> Self {
+        SearchQuery {
+            embedding: query_embedding,
+            k,
+            ef_search: None,
+            normalize: false,
+            softmax_temperature: None,
+            dedup_by_content: false,
+            with_components: false,
+            exclude: HashSet::new(),
+            significance_band: None,
+            semantic_weight: None,
+            graph_weight: None,
+            bio_weight: None,
+            cluster_weight: None,
+        }
+    }
+
+    /// Number of results to return.
+    fn k(mut slf: PyRefMut<'_, Self>, k: usize) -> PyRefMut<'_, Self> {
+        slf.k = k;
+        slf
+    }
+
+    /// HNSW search width (see `hybrid_search`).
+    fn ef_search(mut slf: PyRefMut<'_, Self>, ef_search: usize) -> PyRefMut<'_, Self> {
+        slf.ef_search = Some(ef_search);
+        slf
+    }
+
+    /// Min-max normalize scores over the candidate pool.
+    fn normalize(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.normalize = enabled;
+        slf
+    }
+
+    /// Turn the returned scores into a softmax at this temperature.
+    fn softmax(mut slf: PyRefMut<'_, Self>, temperature: f32) -> PyRefMut<'_, Self> {
+        slf.softmax_temperature = Some(temperature);
+        slf
+    }
+
+    /// Keep only the best-ranked node per content hash.
+    fn dedup_by_content(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.dedup_by_content = enabled;
+        slf
+    }
+
+    /// Also report each result's similarity and life score (see `hybrid_search`).
+    fn with_components(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.with_components = enabled;
+        slf
+    }
+
+    /// Never return these ids (adds to any ids excluded earlier).
+    fn exclude(mut slf: PyRefMut<'_, Self>, ids: Vec<u64>) -> PyRefMut<'_, Self> {
+        slf.exclude.extend(ids);
+        slf
+    }
+
+    /// Only return nodes with significance in `[min_significance, max_significance]`.
+    fn significance_band(mut slf: PyRefMut<'_, Self>, min_significance: u8, max_significance: u8) -> PyRefMut<'_, Self> {
+        slf.significance_band = Some((min_significance, max_significance));
+        slf
+    }
+
+    /// Override the ranking blend for this query; None keeps the database's weight.
+    fn weights(
+        mut slf: PyRefMut<'_, Self>,
+        semantic: Option<f32>,
+        graph: Option<f32>,
+        bio: Option<f32>,
+        cluster: Option<f32>,
+    ) -> PyRefMut<'_, Self> {
+        slf.semantic_weight = semantic.or(slf.semantic_weight);
+        slf.graph_weight = graph.or(slf.graph_weight);
+        slf.bio_weight = bio.or(slf.bio_weight);
+        slf.cluster_weight = cluster.or(slf.cluster_weight);
+        slf
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SearchQuery(dim={}, k={}, exclude={}, significance_band={:?})",
+            self.embedding.len(), self.k, self.exclude.len(), self.significance_band
+        )
+    }
+}
+
+impl SearchQuery {
+    /// True if the query drops some nodes before ranking (exclusions or a
+    /// significance band), which is when adaptive over-fetch applies.
+    pub(crate) fn is_filtered(&self) -> bool {
+        !self.exclude.is_empty() || self.significance_band.is_some()
+    }
+}
+
+/// What `SpiderDB.run` (and `hybrid_search`) returns: (id, score) pairs, or
+/// (id, similarity, life_score, combined) tuples when the query asked for
+/// its score components. Either way sorted by score, best first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchResults {
+    Scores(Vec<(u64, f32)>),
+    Components(Vec<(u64, f32, f32, f32)>),
+}
+
+impl SearchResults {
+    /// The results as (id, score) pairs; with components, score is `combined`.
+    pub fn into_scores(self) -> Vec<(u64, f32)> {
+        match self {
+            SearchResults::Scores(hits) => hits,
+            SearchResults::Components(hits) => hits.into_iter().map(|(id, _, _, combined)| (id, combined)).collect(),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for SearchResults {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            SearchResults::Scores(hits) => hits.into_py(py),
+            SearchResults::Components(hits) => hits.into_py(py),
+        }
+    }
+}
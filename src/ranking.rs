@@ -1,15 +1,33 @@
 use crate::bio;
+use crate::graph;
 use crate::search;
-use crate::storage::NodeHeader;
+use crate::storage::{Embeddings, NodeHeader};
 use crate::cluster::Cluster;
 use std::collections::{HashSet, VecDeque};
 
 /// Configuration for the hybrid ranking system
+#[derive(Debug, Clone)]
 pub struct RankConfig {
     pub semantic_weight: f32,
     pub graph_weight: f32,
     pub bio_weight: f32,
     pub cluster_weight: f32,
+    /// Lower bound applied to the query cosine before blending.
+    /// Cosine is in [-1, 1]; without a floor an anti-correlated node could drag
+    /// its total below unrelated (zero-similarity) nodes. Default 0.0.
+    pub similarity_floor: f32,
+    /// Metric for every similarity that feeds the blend, normalized to the
+    /// cosine-like scale the weights expect. Default cosine.
+    pub metric: search::Metric,
+    /// When set, each edge's contribution to graph scoring and weighted
+    /// traversal halves every this many hours since the edge was created.
+    /// Default None (edges never age).
+    pub edge_half_life_hours: Option<f32>,
+    /// How seed connections turn into the graph score's seed bonus.
+    /// Default `SeedBonus::Fraction`.
+    pub seed_bonus: SeedBonus,
+    /// How a candidate's terms become its score. Default `ScoreCombiner::Sum`.
+    pub combiner: ScoreCombiner,
 }
 
 impl Default for RankConfig {
@@ -19,6 +37,108 @@ impl Default for RankConfig {
             graph_weight: 0.30,
             bio_weight: 0.15,
             cluster_weight: 0.05,
+            similarity_floor: 0.0,
+            metric: search::Metric::Cosine,
+            edge_half_life_hours: None,
+            seed_bonus: SeedBonus::Fraction,
+            combiner: ScoreCombiner::Sum,
+        }
+    }
+}
+
+/// How hybrid search combines a candidate's terms into its score.
+///
+/// `Sum` is the weighted sum of the semantic, graph, bio and cluster terms.
+/// `Product` is the semantic term times the life score (after the
+/// `min_life_score` floor); it ignores the graph and cluster terms and the
+/// weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreCombiner {
+    Sum,
+    Product,
+}
+
+impl ScoreCombiner {
+    /// Parses "sum" or "product" (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sum" => Some(ScoreCombiner::Sum),
+            "product" => Some(ScoreCombiner::Product),
+            _ => None,
+        }
+    }
+}
+
+/// Scaling of the seed bonus in `calculate_graph_score`, from the (decayed)
+/// number of seeds a node connects to and the total number of seeds.
+///
+/// `Fraction` is the plain share of seeds, which shrinks toward zero for a
+/// node tied to one of many seeds. `Floor(f)` gives any seed connection at
+/// least `f`; `Log` grows logarithmically, so the first few connections count
+/// the most.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeedBonus {
+    Fraction,
+    Floor(f32),
+    Log,
+}
+
+impl SeedBonus {
+    /// Parses "fraction", "floor" (needs `floor`) or "log" (case-insensitive).
+    pub fn parse(name: &str, floor: Option<f32>) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "fraction" => Some(SeedBonus::Fraction),
+            "floor" => floor.map(SeedBonus::Floor),
+            "log" => Some(SeedBonus::Log),
+            _ => None,
+        }
+    }
+
+    /// Bonus in [0, 1] for `connected` (possibly fractional) of `total` seeds.
+    pub fn score(&self, connected: f32, total: usize) -> f32 {
+        let total = total.max(1) as f32;
+        let fraction = (connected / total).min(1.0);
+        match *self {
+            SeedBonus::Fraction => fraction,
+            SeedBonus::Floor(floor) if connected > 0.0 => fraction.max(floor.clamp(0.0, 1.0)),
+            SeedBonus::Floor(_) => 0.0,
+            SeedBonus::Log => ((1.0 + connected).ln() / (1.0 + total).ln()).min(1.0),
+        }
+    }
+}
+
+/// Thresholds controlling how far cluster-based candidate selection descends
+#[derive(Debug, Clone, Copy)]
+pub struct CandidateConfig {
+    /// Number of best-matching root clusters to draw from
+    pub max_root_clusters: usize,
+    /// Root clusters at or below this query similarity are skipped
+    pub min_root_similarity: f32,
+    /// A cluster's sub-clusters are only visited when its own similarity exceeds this
+    pub descend_threshold: f32,
+    /// Number of best-matching sub-clusters visited per level
+    pub max_sub_clusters: usize,
+    /// Sub-clusters at or below this query similarity are skipped
+    pub min_sub_similarity: f32,
+    /// When set, the best-matching nodes at or above this significance are
+    /// always injected into the candidate set, so approximate retrieval can't
+    /// narrowly miss them. None (default) disables the boost.
+    pub boost_min_significance: Option<u8>,
+    /// Filtered searches keep widening the fetch until `k` results qualify.
+    /// Default true.
+    pub adaptive_overfetch: bool,
+}
+
+impl Default for CandidateConfig {
+    fn default() -> Self {
+        Self {
+            max_root_clusters: 3,
+            min_root_similarity: 0.4,
+            descend_threshold: 0.5,
+            max_sub_clusters: 2,
+            min_sub_similarity: 0.45,
+            boost_min_significance: None,
+            adaptive_overfetch: true,
         }
     }
 }
@@ -27,7 +147,8 @@ impl Default for RankConfig {
 pub fn find_cluster_candidates(
     clusters: &[Cluster], 
     query_embedding: &[f32], 
-    max_candidates: usize
+    max_candidates: usize,
+    config: &CandidateConfig,
 ) -> Vec<u64> {
     let mut candidates = HashSet::new();
     let mut cluster_scores = Vec::new();
@@ -38,13 +159,13 @@ pub fn find_cluster_candidates(
         cluster_scores.push((cluster, similarity));
     }
 
-    // Sort and take top 3
+    // Sort and take the top few
     cluster_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
     
-    for (cluster, cluster_sim) in cluster_scores.into_iter().take(3) {
-        if cluster_sim <= 0.4 { continue; }
+    for (cluster, cluster_sim) in cluster_scores.into_iter().take(config.max_root_clusters) {
+        if cluster_sim <= config.min_root_similarity { continue; }
         
-        collect_recursive(cluster, query_embedding, &mut candidates, cluster_sim, max_candidates);
+        collect_recursive(cluster, query_embedding, &mut candidates, cluster_sim, max_candidates, config);
         if candidates.len() >= max_candidates { break; }
     }
 
@@ -57,22 +178,23 @@ fn collect_recursive(
     candidates: &mut HashSet<u64>,
     parent_score: f32,
     limit: usize,
+    config: &CandidateConfig,
 ) {
     for &mid in &cluster.member_ids {
         candidates.insert(mid);
         if candidates.len() >= limit { return; }
     }
 
-    if parent_score > 0.5 && !cluster.sub_clusters.is_empty() {
+    if parent_score > config.descend_threshold && !cluster.sub_clusters.is_empty() {
         let mut sub_scores: Vec<_> = cluster.sub_clusters.iter()
             .map(|sc| (sc, search::cosine_similarity(query, &sc.centroid)))
             .collect();
         
         sub_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
 
-        for (sc, score) in sub_scores.into_iter().take(2) {
-            if score > 0.45 {
-                collect_recursive(sc, query, candidates, score, limit);
+        for (sc, score) in sub_scores.into_iter().take(config.max_sub_clusters) {
+            if score > config.min_sub_similarity {
+                collect_recursive(sc, query, candidates, score, limit, config);
                 if candidates.len() >= limit { return; }
             }
         }
@@ -108,27 +230,38 @@ pub fn expand_with_neighbors(
 }
 
 /// 3. Scoring: Calculate Graph Connectivity Score
-pub fn calculate_graph_score(
+///
+/// `edge_decay(node, neighbor)` scales each edge's contribution (1.0 = full
+/// strength), so aged links count for less.
+pub fn calculate_graph_score<D: Fn(u64, u64) -> f32>(
     node_id: u64,
     edge_list: &[Vec<u64>],
-    embeddings: &[Vec<f32>],
+    embeddings: &Embeddings,
     seed_nodes: &[u64],
     query_embedding: &[f32],
+    config: &RankConfig,
+    edge_decay: D,
 ) -> f32 {
-    let neighbors = match edge_list.get(node_id as usize) {
-        Some(n) if !n.is_empty() => n,
-        _ => return 0.0,
+    // Self-loops say nothing about connectivity, so they don't count
+    let neighbors: Vec<u64> = match edge_list.get(node_id as usize) {
+        Some(n) => n.iter().copied().filter(|&n| n != node_id).collect(),
+        None => return 0.0,
     };
+    if neighbors.is_empty() {
+        return 0.0;
+    }
 
-    let connectivity = (neighbors.len() as f32 / 10.0).min(1.0) * 0.3;
+    let degree: f32 = neighbors.iter().map(|&n| edge_decay(node_id, n)).sum();
+    let connectivity = (degree / 10.0).min(1.0) * 0.3;
     
-    let seeds_connected = neighbors.iter()
+    let seeds_connected: f32 = neighbors.iter()
         .filter(|&&n| seed_nodes.contains(&n))
-        .count() as f32;
-    let seed_bonus = (seeds_connected / seed_nodes.len().max(1) as f32) * 0.4;
+        .map(|&n| edge_decay(node_id, n))
+        .sum();
+    let seed_bonus = config.seed_bonus.score(seeds_connected, seed_nodes.len()) * 0.4;
 
     let coherence = calculate_neighborhood_coherence(
-        node_id, neighbors, embeddings, query_embedding
+        node_id, &neighbors, edge_list, embeddings, query_embedding, config.metric, &edge_decay
     ) * 0.3;
 
     (connectivity + seed_bonus + coherence).min(1.0)
@@ -137,30 +270,51 @@ pub fn calculate_graph_score(
 fn calculate_neighborhood_coherence(
     node_id: u64,
     neighbors: &[u64],
-    embeddings: &[Vec<f32>],
+    edge_list: &[Vec<u64>],
+    embeddings: &Embeddings,
     query_embedding: &[f32],
+    metric: search::Metric,
+    edge_decay: &impl Fn(u64, u64) -> f32,
 ) -> f32 {
-    let node_emb = &embeddings[node_id as usize];
+    let node_emb = embeddings.vector(node_id as usize);
     let mut total = 0.0;
-    let limit = neighbors.len().min(5);
+    let mut total_weight = 0.0;
 
-    for &nid in neighbors.iter().take(limit) {
+    // Each neighbor counts in proportion to its edge strength (negative
+    // weights count as zero), so a weak link barely moves the average.
+    for &nid in neighbors.iter().take(5) {
         if nid as usize >= embeddings.len() { continue; }
-        let n_emb = &embeddings[nid as usize];
+        let weight = graph::edge_weight(edge_list, embeddings, node_id, nid)
+            .unwrap_or(0.0)
+            .max(0.0)
+            * edge_decay(node_id, nid);
+        let n_emb = embeddings.vector(nid as usize);
         
-        let q_sim = search::cosine_similarity(query_embedding, n_emb);
-        let n_sim = search::cosine_similarity(node_emb, n_emb);
-        total += (q_sim + n_sim) / 2.0;
+        let q_sim = metric.similarity(query_embedding, &n_emb);
+        let n_sim = metric.similarity(&node_emb, &n_emb);
+        total += weight * (q_sim + n_sim) / 2.0;
+        total_weight += weight;
     }
-    total / limit as f32
+    if total_weight > 0.0 { total / total_weight } else { 0.0 }
 }
 
-/// 4. Scoring: Biological Score
-pub fn calculate_bio_score(header: &NodeHeader) -> f32 {
+/// Steady read rate (reads per day) at which the bio score's frequency term
+/// saturates. The decayed counter of a node read at this rate settles at
+/// ~20 with the default 24h half-life; a longer half-life remembers more
+/// reads, so the cap scales with it rather than being a fixed count.
+const SATURATING_READS_PER_DAY: f32 = 14.0;
+
+/// 4. Scoring: Biological Score (evaluated at time `now`)
+///
+/// `recency` comes from `BioConfig::life_score`, which counts lifetime
+/// accesses damped by idle time; `freq` uses the decayed recent-access
+/// counter, so only it forgets old bursts of traffic.
+pub fn calculate_bio_score(header: &NodeHeader, now: u64, bio_config: &bio::BioConfig) -> f32 {
     let sig_score = header.significance as f32 / 255.0;
-    let life_score = bio::calc_life_score(header);
+    let life_score = bio_config.life_score(header, now).max(bio_config.min_life_score);
     let recency = (life_score / 10.0).min(1.0);
-    let freq = (header.access_count as f32 / 100.0).min(1.0);
+    let saturation = bio_config.steady_recent_access(SATURATING_READS_PER_DAY);
+    let freq = (bio_config.decayed_access(header, now) / saturation).min(1.0);
 
     (sig_score * 0.4) + (recency * 0.3) + (freq * 0.3)
 }
@@ -170,6 +324,7 @@ pub fn calculate_cluster_score(
     node_id: u64,
     clusters: Option<&Vec<Cluster>>,
     query_embedding: &[f32],
+    metric: search::Metric,
 ) -> f32 {
     let clusters = match clusters {
         Some(c) => c,
@@ -178,10 +333,10 @@ pub fn calculate_cluster_score(
 
     let mut best = 0.0;
     // Helper to find node recursively
-    fn check(cid: u64, c: &Cluster, q: &[f32], best: &mut f32) -> bool {
-        let found = c.member_ids.contains(&cid) || c.sub_clusters.iter().any(|sub| check(cid, sub, q, best));
+    fn check(cid: u64, c: &Cluster, q: &[f32], metric: search::Metric, best: &mut f32) -> bool {
+        let found = c.member_ids.contains(&cid) || c.sub_clusters.iter().any(|sub| check(cid, sub, q, metric, best));
         if found {
-            let sim = search::cosine_similarity(q, &c.centroid);
+            let sim = metric.similarity(q, &c.centroid);
             let score = (sim * 0.7) + ((c.significance / 255.0) * 0.3);
             *best = best.max(score);
         }
@@ -189,7 +344,219 @@ pub fn calculate_cluster_score(
     }
 
     for c in clusters {
-        check(node_id, c, query_embedding, &mut best);
+        check(node_id, c, query_embedding, metric, &mut best);
     }
     best
-}
\ No newline at end of file
+}
+
+/// 6. Importance: PageRank whose teleport distribution follows life scores
+///
+/// Instead of teleporting uniformly, random surfers jump to nodes in
+/// proportion to their `BioConfig::life_score`, so fresh/significant nodes
+/// accumulate more rank mass. Dangling nodes redistribute the same way.
+pub fn bio_pagerank(
+    edge_list: &[Vec<u64>],
+    headers: &[NodeHeader],
+    damping: f32,
+    iterations: usize,
+    now: u64,
+    bio_config: &bio::BioConfig,
+) -> Vec<f32> {
+    let n = headers.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // Teleport vector: normalized life scores (uniform if everything is dead);
+    // deleted nodes get no teleport mass
+    let mut teleport: Vec<f32> = headers.iter()
+        .map(|h| if h.is_live() { bio_config.life_score(h, now) } else { 0.0 })
+        .collect();
+    let total: f32 = teleport.iter().sum();
+    if total > 0.0 {
+        for t in &mut teleport {
+            *t /= total;
+        }
+    } else {
+        teleport.iter_mut().for_each(|t| *t = 1.0 / n as f32);
+    }
+
+    let mut rank = teleport.clone();
+    for _ in 0..iterations {
+        let mut next = vec![0.0; n];
+        let mut dangling = 0.0;
+
+        for (src, targets) in edge_list.iter().enumerate().take(n) {
+            // Self-loops would just hand a node's rank back to itself
+            let is_valid = |t: u64| (t as usize) < n && t as usize != src;
+            let valid = targets.iter().filter(|&&t| is_valid(t)).count();
+            if valid == 0 {
+                dangling += rank[src];
+                continue;
+            }
+            let share = rank[src] / valid as f32;
+            for &t in targets {
+                if is_valid(t) {
+                    next[t as usize] += share;
+                }
+            }
+        }
+
+        for (i, value) in next.iter_mut().enumerate() {
+            *value = (1.0 - damping) * teleport[i] + damping * (*value + dangling * teleport[i]);
+        }
+        rank = next;
+    }
+    rank
+}
+
+/// 7. Normalization: Min-max rescale scores into [0, 1]
+///
+/// `min`/`max` should come from the whole candidate pool of the query (not
+/// just the returned slice) so the same threshold means the same thing across
+/// queries. Ordering is preserved; a degenerate range maps everything to 1.0.
+pub fn min_max_normalize(results: &mut [(u64, f32)], min: f32, max: f32) {
+    let range = max - min;
+    for (_, score) in results.iter_mut() {
+        *score = if range > f32::EPSILON {
+            ((*score - min) / range).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+    }
+}
+
+/// 8. Normalization: Temperature-scaled softmax into pseudo-probabilities
+///
+/// Scores become `exp(s / T) / sum(exp(s_i / T))`, so they are positive and sum
+/// to 1.0 while keeping their order. Lower temperatures sharpen the
+/// distribution toward the top result. Non-positive temperatures act as 1.0.
+pub fn softmax(results: &mut [(u64, f32)], temperature: f32) {
+    let temperature = if temperature > 0.0 { temperature } else { 1.0 };
+    // Shift by the max so exp() can't overflow
+    let max = results.iter().map(|&(_, s)| s).fold(f32::MIN, f32::max);
+    let mut sum = 0.0;
+    for (_, score) in results.iter_mut() {
+        *score = ((*score - max) / temperature).exp();
+        sum += *score;
+    }
+    if sum > 0.0 {
+        for (_, score) in results.iter_mut() {
+            *score /= sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(id: u64, significance: u8, now: u64) -> NodeHeader {
+        NodeHeader {
+            id,
+            data_offset: 0,
+            data_len: 0,
+            edge_start: 0,
+            edge_count: 0,
+            last_access_ts: now,
+            access_count: 0,
+            recent_access: 0.0,
+            significance,
+            flags: 0,
+            created_ts: now,
+            ttl_secs: 0,
+        }
+    }
+
+    #[test]
+    fn frequency_term_saturates_at_a_steady_read_rate() {
+        let now = 1_000_000;
+        for hours in [6.0, 24.0, 168.0] {
+            let config = bio::BioConfig { recent_access_half_life_hours: hours, ..Default::default() };
+            let steady = config.steady_recent_access(SATURATING_READS_PER_DAY);
+            let mut busy = header(0, 0, now);
+            busy.recent_access = steady;
+            let mut busier = busy;
+            busier.recent_access = steady * 2.0;
+            let mut quieter = busy;
+            quieter.recent_access = steady / 2.0;
+
+            let score = |h: &NodeHeader| calculate_bio_score(h, now, &config);
+            assert_eq!(score(&busy), score(&busier), "half-life {}", hours);
+            assert!(score(&quieter) < score(&busy), "half-life {}", hours);
+        }
+        let default = bio::BioConfig::default().steady_recent_access(SATURATING_READS_PER_DAY);
+        assert!((default - 20.0).abs() < 0.5, "{}", default);
+    }
+
+    #[test]
+    fn bio_pagerank_favors_higher_life_score() {
+        // 1 and 2 hang off 0 in exactly the same way; only significance differs
+        let now = 1_000_000;
+        let headers = [header(0, 128, now), header(1, 255, now), header(2, 0, now)];
+        let edge_list = vec![vec![1, 2], vec![0], vec![0]];
+
+        let rank = bio_pagerank(&edge_list, &headers, 0.85, 50, now, &bio::BioConfig::default());
+        assert!(rank[1] > rank[2], "{:?}", rank);
+        assert!((rank.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+
+    fn cluster(id: u64, member_ids: Vec<u64>, centroid: Vec<f32>, sub_clusters: Vec<Cluster>) -> Cluster {
+        Cluster { id, anchor_node_id: member_ids[0], member_ids, centroid, significance: 128.0, sub_clusters, depth: 0 }
+    }
+
+    #[test]
+    fn lower_descend_threshold_reaches_sub_clusters() {
+        // The root matches the query at 0.45: kept as a root, but below the default descent gate
+        let subs = vec![
+            cluster(1, vec![1], vec![1.0, 0.0], vec![]),
+            cluster(2, vec![2], vec![0.9, 0.1], vec![]),
+        ];
+        let root = cluster(0, vec![0], vec![0.45, (1.0f32 - 0.45 * 0.45).sqrt()], subs);
+        let query = [1.0, 0.0];
+
+        let mut shallow = find_cluster_candidates(std::slice::from_ref(&root), &query, 10, &CandidateConfig::default());
+        shallow.sort();
+        assert_eq!(shallow, vec![0]);
+
+        let config = CandidateConfig { descend_threshold: 0.4, ..CandidateConfig::default() };
+        let mut deep = find_cluster_candidates(&[root], &query, 10, &config);
+        deep.sort();
+        assert_eq!(deep, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn coherence_weights_neighbors_by_edge_strength() {
+        let at = |angle: f32| vec![angle.cos(), angle.sin()];
+        // Neighbor 1 is strongly tied to node 0, neighbor 2 only weakly
+        let embeddings = vec![at(0.0), at(0.2), at(1.4)];
+        let edge_list = vec![vec![1, 2], vec![0], vec![0]];
+        let query = at(1.4);
+
+        let term = |n: usize| (search::cosine_similarity(&query, &embeddings[n]) + search::cosine_similarity(&embeddings[0], &embeddings[n])) / 2.0;
+        let coherence = calculate_neighborhood_coherence(
+            0, &[1, 2], &edge_list, &embeddings.clone().into(), &query, search::Metric::Cosine, &|_, _| 1.0,
+        );
+        assert!(coherence > (term(1) + term(2)) / 2.0, "{}", coherence);
+        assert!((coherence - term(1)).abs() < (coherence - term(2)).abs(), "{}", coherence);
+    }
+
+    #[test]
+    fn one_of_many_seeds_still_earns_a_bonus() {
+        // Node 0 links to seed 1 only, out of 50 seeds
+        let embeddings = vec![vec![1.0, 0.0]; 51].into();
+        let mut edge_list = vec![Vec::new(); 51];
+        edge_list[0].push(1);
+        let seeds: Vec<u64> = (1..=50).collect();
+        let score = |seed_bonus: SeedBonus| {
+            let config = RankConfig { seed_bonus, ..RankConfig::default() };
+            calculate_graph_score(0, &edge_list, &embeddings, &seeds, &[1.0, 0.0], &config, |_, _| 1.0)
+        };
+
+        let fraction = score(SeedBonus::Fraction);
+        assert!(score(SeedBonus::Floor(0.5)) >= fraction + 0.4 * (0.5 - 1.0 / 50.0) - 1e-6);
+        assert!(score(SeedBonus::Log) > fraction);
+        assert!(SeedBonus::Log.score(1.0, 50) > 0.15);
+        assert_eq!(SeedBonus::Floor(0.5).score(0.0, 50), 0.0);
+    }
+}
@@ -1,8 +1,18 @@
 use hnsw_rs::prelude::*;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use wide::f32x8;
 
 /// A wrapper around the HNSW index.
 pub struct VectorIndex {
     index: Hnsw<'static, f32, DistCosine>,
+    m: usize,
+    max_elements: usize,
+    ef_construction: usize,
+    /// Number of vectors inserted so far.
+    len: usize,
+    /// Superseded vectors still in the graph after `replace`
+    stale: usize,
 }
 
 impl VectorIndex {
@@ -25,41 +35,489 @@ impl VectorIndex {
             DistCosine
         );
         
-        VectorIndex { index }
+        VectorIndex { index, m, max_elements, ef_construction, len: 0, stale: 0 }
     }
 
     /// Adds a vector to the index.
-    pub fn add(&self, id: u64, vector: &[f32]) {
+    ///
+    /// The HNSW graph is sized for `capacity()` vectors; callers must `grow`
+    /// once `is_full()` or later vectors won't be reliably searchable.
+    pub fn add(&mut self, id: u64, vector: &[f32]) {
         // hnsw_rs uses usize for ID. We cast u64 to usize.
         // Ensure ID fits in usize (safe on 64-bit systems).
         self.index.insert((vector, id as usize));
+        self.len += 1;
+    }
+
+    /// Inserts a new vector for an already indexed `id`.
+    ///
+    /// HNSW can't delete, so the old vector stays in the graph: `search`
+    /// drops its duplicate hits, but a hit on the old vector reports the old
+    /// similarity, so callers should re-score when `stale() > 0`. The old
+    /// vector takes up capacity until the next `rebuild`/`grow`.
+    pub fn replace(&mut self, id: u64, vector: &[f32]) {
+        self.index.insert((vector, id as usize));
+        self.stale += 1;
+    }
+
+    /// Number of superseded vectors left in the graph by `replace`.
+    pub fn stale(&self) -> usize {
+        self.stale
+    }
+
+    /// Number of vectors inserted so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True if nothing has been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maximum number of vectors the index was sized for.
+    pub fn capacity(&self) -> usize {
+        self.max_elements
+    }
+
+    /// True once another `add` would exceed `capacity()`.
+    pub fn is_full(&self) -> bool {
+        self.len + self.stale >= self.max_elements
+    }
+
+    /// Rebuilds the index at (at least) double capacity from `vectors`,
+    /// where each vector's position is its id. HNSW can't be resized in place.
+    pub fn grow(&mut self, vectors: &[Vec<f32>]) {
+        let capacity = (self.max_elements * 2).max(vectors.len() + 1);
+        self.rebuild_with_capacity(vectors, capacity);
+    }
+
+    /// Rebuilds the index from scratch from `vectors` (position = id), keeping
+    /// the current capacity unless more room is needed.
+    pub fn rebuild(&mut self, vectors: &[Vec<f32>]) {
+        let capacity = self.max_elements.max(vectors.len() + 1);
+        self.rebuild_with_capacity(vectors, capacity);
+    }
+
+    /// Like `rebuild`, but with new HNSW parameters where given.
+    pub fn reconfigure(&mut self, vectors: &[Vec<f32>], m: Option<usize>, ef_construction: Option<usize>) {
+        self.m = m.unwrap_or(self.m);
+        self.ef_construction = ef_construction.unwrap_or(self.ef_construction);
+        self.rebuild(vectors);
+    }
+
+    fn rebuild_with_capacity(&mut self, vectors: &[Vec<f32>], capacity: usize) {
+        let mut rebuilt = VectorIndex::new(Some(self.m), Some(capacity), Some(self.ef_construction));
+        for (id, vector) in vectors.iter().enumerate() {
+            rebuilt.add(id as u64, vector);
+        }
+        *self = rebuilt;
     }
 
     /// Searches for the nearest neighbors.
+    ///
+    /// `k` is clamped to the number of indexed vectors, so asking for more
+    /// than exist just returns all of them. Each id appears at most once.
     pub fn search(&self, query: &[f32], k: usize, ef_search: Option<usize>) -> Vec<(u64, f32)> {
-        let ef_search = ef_search.unwrap_or(64); // Search parameter
-        let results = self.index.search(query, k, ef_search);
+        let k = k.min(self.len);
+        if k == 0 {
+            return Vec::new();
+        }
+        // Over-fetch by the replaced vectors, which may shadow live ids
+        let fetch = k + self.stale;
+        let ef_search = ef_search.unwrap_or(64).min((self.len + self.stale).max(fetch)); // Search parameter
+        let results = self.index.search(query, fetch, ef_search);
         
         // hnsw_rs returns (Neighbor { d_id, distance, ... })
         // We want (id, similarity).
         // DistCosine in hnsw_rs usually returns Cosine Distance (0 to 2).
         // Similarity = 1.0 - Distance.
         
-        results.into_iter().map(|neighbor| {
-            (neighbor.d_id as u64, 1.0 - neighbor.distance)
-        }).collect()
+        let mut seen = std::collections::HashSet::new();
+        results.into_iter()
+            .filter(|neighbor| seen.insert(neighbor.d_id))
+            .take(k)
+            .map(|neighbor| (neighbor.d_id as u64, 1.0 - neighbor.distance))
+            .collect()
+    }
+}
+
+/// What hybrid search does with a query it can't rank meaningfully: one whose
+/// norm is zero (or not finite), or whose dimension differs from the stored
+/// embeddings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidQueryMode {
+    /// Raise ValueError
+    Raise,
+    /// Return no results
+    Empty,
+}
+
+impl InvalidQueryMode {
+    /// Parses "raise" or "empty" (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "raise" => Some(InvalidQueryMode::Raise),
+            "empty" => Some(InvalidQueryMode::Empty),
+            _ => None,
+        }
+    }
+}
+
+/// Similarity metric used when ranking blends scores.
+///
+/// The HNSW index always uses cosine for candidate retrieval; the metric only
+/// changes how candidates are scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cosine,
+    Dot,
+    L2,
+}
+
+impl Metric {
+    /// Parses "cosine", "dot" or "l2" (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "cosine" => Some(Metric::Cosine),
+            "dot" => Some(Metric::Dot),
+            "l2" => Some(Metric::L2),
+            _ => None,
+        }
+    }
+
+    /// Raw metric value: cosine similarity, dot product, or Euclidean distance.
+    pub fn raw(&self, v1: &[f32], v2: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => cosine_similarity(v1, v2),
+            Metric::Dot => v1.iter().zip(v2).map(|(a, b)| a * b).sum(),
+            Metric::L2 => v1.iter().zip(v2).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt(),
+        }
+    }
+
+    /// Maps a raw value onto the higher-is-closer scale the ranking weights
+    /// were tuned for, which is plain cosine.
+    ///
+    /// Cosine therefore passes through unchanged and stays in [-1, 1]
+    /// (`RankConfig::similarity_floor` clips the negative end). Only the other
+    /// metrics are squashed into (0, 1]: a dot product goes through a logistic
+    /// curve, and an L2 distance `d` becomes `1 / (1 + d)`.
+    pub fn normalize(&self, raw: f32) -> f32 {
+        match self {
+            Metric::Cosine => raw,
+            Metric::Dot => 1.0 / (1.0 + (-raw).exp()),
+            Metric::L2 => 1.0 / (1.0 + raw.max(0.0)),
+        }
+    }
+
+    /// `raw` followed by `normalize`.
+    pub fn similarity(&self, v1: &[f32], v2: &[f32]) -> f32 {
+        self.normalize(self.raw(v1, v2))
     }
 }
 
 /// Calculates the cosine similarity between two vectors.
+///
+/// The shared prefix is processed 8 lanes at a time (`wide` picks SSE/AVX/NEON
+/// or a scalar emulation per target); the remaining elements go through the
+/// scalar loop. Summation order differs from a plain loop, so results can
+/// differ from it in the last few bits.
 pub fn cosine_similarity(v1: &[f32], v2: &[f32]) -> f32 {
-    let dot_product: f32 = v1.iter().zip(v2).map(|(a, b)| a * b).sum();
-    let norm_a: f32 = v1.iter().map(|a| a * a).sum::<f32>().sqrt();
-    let norm_b: f32 = v2.iter().map(|b| b * b).sum::<f32>().sqrt();
-    
+    let n = v1.len().min(v2.len());
+    let (head_a, tail_a) = v1.split_at(n - n % LANES);
+    let (head_b, tail_b) = v2.split_at(n - n % LANES);
+
+    let mut dot = f32x8::ZERO;
+    let mut sq_a = f32x8::ZERO;
+    let mut sq_b = f32x8::ZERO;
+    for (a, b) in head_a.chunks_exact(LANES).zip(head_b.chunks_exact(LANES)) {
+        let a = f32x8::from(<[f32; LANES]>::try_from(a).unwrap());
+        let b = f32x8::from(<[f32; LANES]>::try_from(b).unwrap());
+        dot = a.mul_add(b, dot);
+        sq_a = a.mul_add(a, sq_a);
+        sq_b = b.mul_add(b, sq_b);
+    }
+
+    let dot_product = dot.reduce_add() + tail_a.iter().zip(tail_b).map(|(a, b)| a * b).sum::<f32>();
+    let norm_a = (sq_a.reduce_add() + tail_a.iter().map(|a| a * a).sum::<f32>()).sqrt();
+    let norm_b = (sq_b.reduce_add() + tail_b.iter().map(|b| b * b).sum::<f32>()).sqrt();
+
     if norm_a == 0.0 || norm_b == 0.0 {
         return 0.0;
     }
-    
+
     dot_product / (norm_a * norm_b)
 }
+
+/// SIMD width used by `cosine_similarity`.
+const LANES: usize = 8;
+
+/// Literal text match used by `search_text`.
+///
+/// With `case_insensitive` both sides are Unicode-lowercased first. With
+/// `whole_word` an occurrence only counts if the characters on either side
+/// (if any) are not alphanumeric or `_`, so "Error" doesn't match "Errors".
+pub fn text_contains(haystack: &str, needle: &str, case_insensitive: bool, whole_word: bool) -> bool {
+    let (haystack, needle) = if case_insensitive {
+        (haystack.to_lowercase(), needle.to_lowercase())
+    } else {
+        (haystack.to_string(), needle.to_string())
+    };
+    if !whole_word {
+        return haystack.contains(&needle);
+    }
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    haystack.match_indices(&needle).any(|(start, matched)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + matched.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+/// Lowercased alphanumeric terms of `text` (anything else separates terms).
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// BM25-style lexical relevance of `text` to `query_terms`, in [0, 1).
+///
+/// Each distinct query term contributes `tf / (tf + 1.2)` (saturating term
+/// frequency, BM25's k1), averaged over the query terms. There is no IDF or
+/// length normalization; this is a cheap tie-in for exact keyword hits.
+pub fn lexical_score(text: &str, query_terms: &[String]) -> f32 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let terms = tokenize(text);
+    let total: f32 = query_terms.iter()
+        .map(|q| {
+            let tf = terms.iter().filter(|t| *t == q).count() as f32;
+            tf / (tf + 1.2)
+        })
+        .sum();
+    total / query_terms.len() as f32
+}
+
+/// (score, id) pair ordered by score so it can live in a `BinaryHeap`.
+struct Scored(f32, u64);
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Ties go to the lower id so selection is deterministic.
+        self.0.total_cmp(&other.0).then_with(|| other.1.cmp(&self.1))
+    }
+}
+
+/// Brute-force top-`k` by cosine: (index into `embeddings`, similarity),
+/// sorted descending. Embeddings whose length differs from `query` are
+/// skipped, and an empty query or arena gives no results.
+pub fn find_similar_vectors(query: &[f32], embeddings: &[Vec<f32>], k: usize) -> Vec<(usize, f32)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let scored = embeddings.iter()
+        .enumerate()
+        .filter(|(_, v)| v.len() == query.len())
+        .map(|(i, v)| (i as u64, cosine_similarity(query, v)));
+    top_k(scored, k).into_iter().map(|(i, sim)| (i as usize, sim)).collect()
+}
+
+/// Selects the `k` highest-scoring (id, score) pairs, sorted descending.
+///
+/// Uses a bounded min-heap of size `k`, so this is O(n log k) instead of the
+/// O(n log n) full sort, and never holds more than `k` entries.
+pub fn top_k<I: IntoIterator<Item = (u64, f32)>>(items: I, k: usize) -> Vec<(u64, f32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // Don't trust `k` for the allocation: callers may pass "everything" as usize::MAX
+    let items = items.into_iter();
+    let capacity = k.min(items.size_hint().1.unwrap_or(1024)).saturating_add(1);
+    let mut heap: BinaryHeap<Reverse<Scored>> = BinaryHeap::with_capacity(capacity);
+    for (id, score) in items {
+        let entry = Reverse(Scored(score, id));
+        if heap.len() < k {
+            heap.push(entry);
+        } else if heap.peek().is_some_and(|min| entry < *min) {
+            // `Reverse` flips the order: smaller means a higher score
+            heap.pop();
+            heap.push(entry);
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(Scored(score, id))| (id, score))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Pseudo-random scores with plenty of exact ties (two decimal places).
+    fn scores(n: usize) -> Vec<(u64, f32)> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..n as u64).map(|id| (id, (rng.gen_range(0..1000) as f32) / 100.0)).collect()
+    }
+
+    /// The full sort `top_k` replaces: score descending, ties to the lower id.
+    fn sorted_top_k(mut items: Vec<(u64, f32)>, k: usize) -> Vec<(u64, f32)> {
+        items.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        items.truncate(k);
+        items
+    }
+
+    #[test]
+    fn top_k_matches_full_sort() {
+        let items = scores(50_000);
+        for k in [0, 1, 10, 1000, 60_000] {
+            assert_eq!(top_k(items.iter().copied(), k), sorted_top_k(items.clone(), k), "k = {}", k);
+        }
+    }
+
+    /// Selection time against a full sort; run with
+    /// `cargo test --release -- --ignored --nocapture top_k_bench`.
+    #[test]
+    #[ignore]
+    fn top_k_bench() {
+        let items = scores(2_000_000);
+        let start = std::time::Instant::now();
+        let heap = top_k(items.iter().copied(), 10);
+        let heap_time = start.elapsed();
+        let start = std::time::Instant::now();
+        let sorted = sorted_top_k(items, 10);
+        let sort_time = start.elapsed();
+
+        println!("top_k: {:?}, full sort: {:?}", heap_time, sort_time);
+        assert_eq!(heap, sorted);
+        assert!(heap_time < sort_time);
+    }
+
+    /// The plain loop `cosine_similarity` vectorizes.
+    fn scalar_cosine(v1: &[f32], v2: &[f32]) -> f32 {
+        let dot: f32 = v1.iter().zip(v2).map(|(a, b)| a * b).sum();
+        let norm_a = v1.iter().map(|a| a * a).sum::<f32>().sqrt();
+        let norm_b = v2.iter().map(|b| b * b).sum::<f32>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 { 0.0 } else { dot / (norm_a * norm_b) }
+    }
+
+    fn random_vectors(n: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n).map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect()
+    }
+
+    #[test]
+    fn simd_cosine_matches_scalar() {
+        // Lane multiples, pure tails and mixes of both
+        for dim in [1, 7, 8, 13, 64, 768, 771] {
+            let vectors = random_vectors(20, dim, dim as u64);
+            for pair in vectors.windows(2) {
+                let (simd, scalar) = (cosine_similarity(&pair[0], &pair[1]), scalar_cosine(&pair[0], &pair[1]));
+                assert!((simd - scalar).abs() < 1e-5, "dim {}: {} vs {}", dim, simd, scalar);
+            }
+        }
+        assert_eq!(cosine_similarity(&[0.0; 9], &[1.0; 9]), 0.0);
+    }
+
+    /// SIMD against scalar cosine on 768-dim vectors; run with
+    /// `cargo test --release -- --ignored --nocapture cosine_bench`.
+    #[test]
+    #[ignore]
+    fn cosine_bench() {
+        let vectors = random_vectors(20_000, 768, 1);
+        let query = &vectors[0];
+        let start = std::time::Instant::now();
+        let simd: f32 = vectors.iter().map(|v| cosine_similarity(query, v)).sum();
+        let simd_time = start.elapsed();
+        let start = std::time::Instant::now();
+        let scalar: f32 = vectors.iter().map(|v| scalar_cosine(query, v)).sum();
+        let scalar_time = start.elapsed();
+
+        println!("simd: {:?}, scalar: {:?}", simd_time, scalar_time);
+        assert!((simd - scalar).abs() < 1e-2);
+        assert!(simd_time < scalar_time);
+    }
+
+    /// Mean top-10 overlap between the HNSW index and an exact scan, over
+    /// `queries` of the vectors themselves.
+    fn index_recall(vectors: &[Vec<f32>], queries: usize, ef_construction: usize, ef_search: usize) -> f32 {
+        let mut index = VectorIndex::new(None, Some(vectors.len() + 1), Some(ef_construction));
+        index.rebuild(vectors);
+        let hits: usize = vectors.iter().step_by(vectors.len() / queries).take(queries)
+            .map(|query| {
+                let exact = top_k(vectors.iter().enumerate().map(|(id, v)| (id as u64, cosine_similarity(query, v))), 10);
+                let approx: Vec<u64> = index.search(query, 10, Some(ef_search)).into_iter().map(|(id, _)| id).collect();
+                exact.iter().filter(|(id, _)| approx.contains(id)).count()
+            })
+            .sum();
+        hits as f32 / (queries * 10) as f32
+    }
+
+    #[test]
+    fn index_top_k_matches_brute_force() {
+        let recall = index_recall(&random_vectors(1_000, 16, 7), 20, 64, 64);
+        assert!(recall >= 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn replaced_vectors_do_not_duplicate_hits() {
+        let mut index = VectorIndex::new(None, Some(16), None);
+        index.add(0, &[1.0, 0.0]);
+        index.add(1, &[0.0, 1.0]);
+        index.replace(0, &[0.9, 0.1]);
+        index.replace(0, &[0.8, 0.2]);
+
+        assert_eq!((index.len(), index.stale()), (2, 2));
+        let ids: Vec<u64> = index.search(&[1.0, 0.0], 2, None).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    /// Index recall and build time on 50k vectors; run with
+    /// `cargo test --release -- --ignored --nocapture index_recall_bench`.
+    #[test]
+    #[ignore]
+    fn index_recall_bench() {
+        let vectors = random_vectors(50_000, 64, 3);
+        let start = std::time::Instant::now();
+        let recall = index_recall(&vectors, 50, 200, 128);
+        println!("recall@10: {}, build + queries: {:?}", recall, start.elapsed());
+        assert!(recall >= 0.9);
+    }
+
+    #[test]
+    fn find_similar_vectors_orders_by_cosine() {
+        let embeddings = vec![vec![0.0, 1.0], vec![1.0, 0.0], vec![0.7, 0.7]];
+        let found = find_similar_vectors(&[1.0, 0.1], &embeddings, 3);
+        assert_eq!(found.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![1, 2, 0]);
+        assert!(found.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(find_similar_vectors(&[1.0, 0.1], &embeddings, 1).len(), 1);
+
+        // Empty input gives nothing; vectors of another length are skipped
+        assert!(find_similar_vectors(&[], &embeddings, 3).is_empty());
+        assert!(find_similar_vectors(&[1.0, 0.0], &[], 3).is_empty());
+        let ragged = vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0]];
+        assert_eq!(find_similar_vectors(&[1.0, 0.0], &ragged, 3).iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![1]);
+    }
+}
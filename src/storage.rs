@@ -1,4 +1,36 @@
+use crate::error::SpiderError;
 use serde::{Serialize, Deserialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Significance used when a caller does not supply one (scale is 0-255)
+pub const DEFAULT_SIGNIFICANCE: u8 = 128;
+
+/// Maps `value` on a caller's 0..=`scale_max` significance scale onto the
+/// stored 0-255 scale, rounding to nearest: 9 on a 0-9 scale is 255, 0 is 0.
+///
+/// Fails if `scale_max` isn't a positive finite number or `value` lies
+/// outside `[0, scale_max]`.
+pub fn significance_from_scale(value: f32, scale_max: f32) -> Result<u8, SpiderError> {
+    if !scale_max.is_finite() || scale_max <= 0.0 {
+        return Err(SpiderError::InvalidConfig(format!("scale_max must be a finite value > 0, got {}", scale_max)));
+    }
+    if !(0.0..=scale_max).contains(&value) {
+        return Err(SpiderError::InvalidConfig(format!("significance {} is outside the 0-{} scale", value, scale_max)));
+    }
+    Ok((value / scale_max * 255.0).round() as u8)
+}
+
+/// `NodeHeader::flags` bit: content is stored deflate-compressed in the heap.
+pub const FLAG_COMPRESSED: u8 = 1 << 0;
+/// `NodeHeader::flags` bit: node was deleted. Its id stays reserved (ids are
+/// arena indices) but it has no edges and is skipped everywhere.
+pub const FLAG_DELETED: u8 = 1 << 1;
+/// `NodeHeader::flags` bit: node is pinned and exempt from vacuum, TTL expiry
+/// and the retention policy.
+pub const FLAG_PINNED: u8 = 1 << 2;
 
 /// Fixed-size metadata for a node.
 /// This struct is designed to be FFI-safe and memory efficient.
@@ -19,6 +51,338 @@ pub struct NodeHeader {
     pub last_access_ts: u64,
     /// Number of times this node has been accessed.
     pub access_count: u32,
+    /// Exponentially-decayed access count as of `last_access_ts`
+    /// (see `bio::BioConfig::decayed_access`), so old traffic fades out.
+    pub recent_access: f32,
     /// Significance score of the node (0-255).
     pub significance: u8,
+    /// Bit flags describing how the node is stored (see `FLAG_*`).
+    pub flags: u8,
+    /// Creation time (Unix timestamp in seconds).
+    pub created_ts: u64,
+    /// Lifetime in seconds counted from `created_ts`; 0 means no TTL.
+    pub ttl_secs: u32,
+}
+
+impl NodeHeader {
+    /// False once the node has been deleted (tombstoned).
+    pub fn is_live(&self) -> bool {
+        self.flags & FLAG_DELETED == 0
+    }
+
+    /// True if the node is pinned (see `FLAG_PINNED`).
+    pub fn is_pinned(&self) -> bool {
+        self.flags & FLAG_PINNED != 0
+    }
+
+    /// True once the node's TTL (if any) has run out at time `now`.
+    /// Pinned nodes never expire.
+    pub fn is_expired(&self, now: u64) -> bool {
+        !self.is_pinned()
+            && self.ttl_secs > 0 && now >= self.created_ts.saturating_add(self.ttl_secs as u64)
+    }
+}
+
+/// `NodeHeader` as stored by format version 0 (snapshots without the magic
+/// header). Only read, to migrate old files.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct NodeHeaderV0 {
+    pub id: u64,
+    pub data_offset: u64,
+    pub data_len: u32,
+    pub edge_start: u32,
+    pub edge_count: u32,
+    pub last_access_ts: u64,
+    pub access_count: u32,
+    pub significance: u8,
+}
+
+impl From<NodeHeaderV0> for NodeHeader {
+    /// Fields added since version 0 get the values that keep old nodes
+    /// behaving as before: not compressed, deleted or pinned; no TTL; created
+    /// at their last access (the earliest time the file still records); and
+    /// every past access counted as recent as of that last access.
+    fn from(old: NodeHeaderV0) -> Self {
+        NodeHeader {
+            id: old.id,
+            data_offset: old.data_offset,
+            data_len: old.data_len,
+            edge_start: old.edge_start,
+            edge_count: old.edge_count,
+            last_access_ts: old.last_access_ts,
+            access_count: old.access_count,
+            recent_access: old.access_count as f32,
+            significance: old.significance,
+            flags: 0,
+            created_ts: old.last_access_ts,
+            ttl_secs: 0,
+        }
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of node content.
+///
+/// Used for content lookups/dedup. Unlike `DefaultHasher` it is stable across
+/// Rust versions, so hashes can be compared between processes.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &b| (hash ^ b as u64).wrapping_mul(PRIME))
+}
+
+/// Deflate-compresses node content.
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing into a Vec cannot fail
+    encoder.write_all(bytes).expect("in-memory write");
+    encoder.finish().expect("in-memory write")
+}
+
+/// Inflates content produced by `compress`. None if the stream is corrupt.
+pub fn decompress(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    flate2::read::DeflateDecoder::new(bytes).read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+/// Least-recently-used cache of inflated content for compressed nodes, so hot
+/// nodes aren't decompressed on every read. Keyed by node id; callers must
+/// `invalidate` an id whose content changes and `clear` when ids are remapped.
+#[derive(Debug)]
+pub struct ContentCache {
+    capacity: usize,
+    /// id -> (content, tick of last use)
+    entries: HashMap<u64, (Arc<[u8]>, u64)>,
+    tick: u64,
+    /// Reads served from the cache
+    pub hits: u64,
+    /// Reads that had to decompress
+    pub misses: u64,
+}
+
+impl ContentCache {
+    /// A cache holding up to `capacity` contents (0 disables caching).
+    pub fn new(capacity: usize) -> Self {
+        ContentCache { capacity, entries: HashMap::new(), tick: 0, hits: 0, misses: 0 }
+    }
+
+    /// Cached content of `id`, marking it most recently used.
+    pub fn get(&mut self, id: u64) -> Option<Arc<[u8]>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (bytes, used) = self.entries.get_mut(&id)?;
+        *used = tick;
+        Some(bytes.clone())
+    }
+
+    /// Caches `bytes` for `id`, evicting the least recently used entry if full.
+    pub fn insert(&mut self, id: u64, bytes: Arc<[u8]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&id) {
+            // Linear scan: the cache is meant to stay small
+            if let Some(&oldest) = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(id, _)| id) {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(id, (bytes, self.tick));
+    }
+
+    /// Forgets `id`'s cached content.
+    pub fn invalidate(&mut self, id: u64) {
+        self.entries.remove(&id);
+    }
+
+    /// Forgets everything and sets a new capacity. Counters are kept.
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.clear();
+    }
+
+    /// Forgets everything.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Backing bytes of the content heap: an owned buffer, or a read-only memory
+/// map of the heap section of a snapshot file (see `SpiderDB.open_mmap`).
+///
+/// Reads go through `Deref<Target = [u8]>` either way. A mapped heap is copied
+/// into memory the first time it is written to (`to_mut`), so the mapping
+/// only pays off for read-mostly databases.
+pub enum Heap {
+    Owned(Vec<u8>),
+    Mapped {
+        map: Arc<memmap2::Mmap>,
+        /// Byte range of the heap within the mapped file
+        start: usize,
+        len: usize,
+    },
+}
+
+impl Heap {
+    /// True while the heap is still served from the memory map.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Heap::Mapped { .. })
+    }
+
+    /// The heap as a growable buffer, copying a mapped heap into memory first.
+    pub fn to_mut(&mut self) -> &mut Vec<u8> {
+        if let Heap::Mapped { map, start, len } = self {
+            *self = Heap::Owned(map[*start..*start + *len].to_vec());
+        }
+        match self {
+            Heap::Owned(bytes) => bytes,
+            Heap::Mapped { .. } => unreachable!(),
+        }
+    }
+}
+
+impl std::ops::Deref for Heap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Heap::Owned(bytes) => bytes,
+            Heap::Mapped { map, start, len } => &map[*start..*start + *len],
+        }
+    }
+}
+
+/// The embedding arena: vectors held in memory, or read out of the embeddings
+/// section of a snapshot file's memory map (see `SpiderDB.open_mmap`).
+///
+/// Mapped floats sit unaligned in the bincode body, so each read copies its
+/// vector out of the map. Like `Heap`, a mapped arena is copied into memory
+/// the first time it is written to (`to_mut`).
+pub enum Embeddings {
+    Owned(Vec<Vec<f32>>),
+    Mapped {
+        map: Arc<memmap2::Mmap>,
+        /// Byte offset in the map and length (in floats) of each vector
+        slots: Vec<(usize, usize)>,
+    },
+}
+
+impl Embeddings {
+    /// True while the vectors are still served from the memory map.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Embeddings::Mapped { .. })
+    }
+
+    /// Number of stored vectors.
+    pub fn len(&self) -> usize {
+        match self {
+            Embeddings::Owned(vectors) => vectors.len(),
+            Embeddings::Mapped { slots, .. } => slots.len(),
+        }
+    }
+
+    /// True if no vectors are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Dimension of the first vector, if any.
+    pub fn dim(&self) -> Option<usize> {
+        match self {
+            Embeddings::Owned(vectors) => vectors.first().map(Vec::len),
+            Embeddings::Mapped { slots, .. } => slots.first().map(|&(_, len)| len),
+        }
+    }
+
+    /// Vector `id`, borrowed when in memory and copied out of the map
+    /// otherwise; None when out of range.
+    pub fn get(&self, id: usize) -> Option<Cow<'_, [f32]>> {
+        match self {
+            Embeddings::Owned(vectors) => vectors.get(id).map(|v| Cow::Borrowed(v.as_slice())),
+            Embeddings::Mapped { map, slots } => {
+                let &(offset, len) = slots.get(id)?;
+                let floats = map[offset..offset + len * 4]
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                Some(Cow::Owned(floats))
+            }
+        }
+    }
+
+    /// Like `get`, but panics on an out-of-range id, as slice indexing does.
+    pub fn vector(&self, id: usize) -> Cow<'_, [f32]> {
+        self.get(id).unwrap_or_else(|| panic!("embedding {} out of range ({} stored)", id, self.len()))
+    }
+
+    /// All vectors, borrowed when in memory and copied out of the map
+    /// otherwise (the copy lives only as long as the returned value).
+    pub fn to_vecs(&self) -> Cow<'_, [Vec<f32>]> {
+        match self {
+            Embeddings::Owned(vectors) => Cow::Borrowed(vectors),
+            Embeddings::Mapped { .. } => Cow::Owned((0..self.len()).map(|id| self.vector(id).into_owned()).collect()),
+        }
+    }
+
+    /// The vectors as a growable list, copying mapped ones into memory first.
+    pub fn to_mut(&mut self) -> &mut Vec<Vec<f32>> {
+        if self.is_mapped() {
+            *self = Embeddings::Owned(self.to_vecs().into_owned());
+        }
+        match self {
+            Embeddings::Owned(vectors) => vectors,
+            Embeddings::Mapped { .. } => unreachable!(),
+        }
+    }
+}
+
+impl From<Vec<Vec<f32>>> for Embeddings {
+    fn from(vectors: Vec<Vec<f32>>) -> Self {
+        Embeddings::Owned(vectors)
+    }
+}
+
+/// Length of one bincode-encoded `Vec<f32>`, read without keeping its floats:
+/// deserializing a snapshot's embeddings as `Vec<SkippedVector>` gives the
+/// lengths `open_mmap` needs to locate each vector in the mapped file.
+pub struct SkippedVector(pub usize);
+
+impl<'de> Deserialize<'de> for SkippedVector {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Counter;
+
+        impl<'de> serde::de::Visitor<'de> for Counter {
+            type Value = SkippedVector;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence of f32")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<SkippedVector, A::Error> {
+                let mut len = 0;
+                while seq.next_element::<f32>()?.is_some() {
+                    len += 1;
+                }
+                Ok(SkippedVector(len))
+            }
+        }
+
+        deserializer.deserialize_seq(Counter)
+    }
+}
+
+/// Returns a node's logical content, decompressing it if needed.
+///
+/// Borrows from `heap` for uncompressed nodes, so the common case is zero-copy.
+pub fn read_content<'a>(header: &NodeHeader, heap: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+    let start = header.data_offset as usize;
+    let end = start + header.data_len as usize;
+    let stored = heap.get(start..end)?;
+
+    if header.flags & FLAG_COMPRESSED != 0 {
+        decompress(stored).map(Cow::Owned)
+    } else {
+        Some(Cow::Borrowed(stored))
+    }
 }